@@ -0,0 +1,45 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod std_layout;
+mod vertex;
+
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    vertex::expand(&input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    std_layout::expand(&input, std_layout::Layout::Std140).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+#[proc_macro_derive(Std430)]
+pub fn derive_std430(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    std_layout::expand(&input, std_layout::Layout::Std430).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+fn check_repr_c(input: &DeriveInput, derive: &str) -> syn::Result<()> {
+    let mut is_repr_c = false;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                is_repr_c = true;
+            }
+            Ok(())
+        })?;
+    }
+
+    if !is_repr_c {
+        return Err(syn::Error::new_spanned(&input.ident, format!("#[derive({})] requires #[repr(C)]", derive)));
+    }
+
+    Ok(())
+}
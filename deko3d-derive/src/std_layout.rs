@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Result};
+
+#[derive(Copy, Clone)]
+pub enum Layout {
+    Std140,
+    Std430,
+}
+
+impl Layout {
+    fn name(self) -> &'static str {
+        match self {
+            Layout::Std140 => "std140",
+            Layout::Std430 => "std430",
+        }
+    }
+}
+
+pub fn expand(input: &DeriveInput, layout: Layout) -> Result<TokenStream> {
+    let name = &input.ident;
+    let layout_name = layout.name();
+    let (trait_name, align_const) = match layout {
+        Layout::Std140 => (format_ident!("Std140"), format_ident!("STD140_ALIGN")),
+        Layout::Std430 => (format_ident!("Std430"), format_ident!("STD430_ALIGN")),
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            format!("#[derive({})] does not support generic structs", trait_name),
+        ));
+    }
+
+    crate::check_repr_c(input, &trait_name.to_string())?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!("#[derive({})] requires a struct with named fields", trait_name),
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(name, format!("#[derive({})] can only be used on structs", trait_name)))
+        }
+    };
+
+    let mut checks = Vec::new();
+    let mut aligns = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let message = format!("field `{}` is not aligned to its {} base alignment", ident, layout_name);
+
+        checks.push(quote! {
+            assert!(
+                ::core::mem::offset_of!(#name, #ident) % <#ty as ::deko3d::#trait_name>::#align_const == 0,
+                #message,
+            );
+        });
+
+        aligns.push(quote! {
+            if <#ty as ::deko3d::#trait_name>::#align_const > align {
+                align = <#ty as ::deko3d::#trait_name>::#align_const;
+            }
+        });
+    }
+
+    if let Layout::Std140 = layout {
+        let message = format!("`{}` exceeds the maximum uniform buffer size", name);
+
+        checks.push(quote! {
+            assert!(::core::mem::size_of::<#name>() <= ::deko3d::DK_UNIFORM_BUF_MAX_SIZE as usize, #message);
+        });
+    }
+
+    // std140 rounds the base alignment of structures up to that of a vec4.
+    let min_align: usize = match layout {
+        Layout::Std140 => 16,
+        Layout::Std430 => 1,
+    };
+    let padding_message = format!("`{}` must be padded to a multiple of its {} base alignment", name, layout_name);
+
+    Ok(quote! {
+        const _: () = {
+            #(#checks)*
+        };
+
+        unsafe impl ::deko3d::#trait_name for #name {
+            const #align_const: usize = {
+                let mut align = #min_align;
+                #(#aligns)*
+                assert!(::core::mem::size_of::<#name>() % align == 0, #padding_message);
+                align
+            };
+        }
+    })
+}
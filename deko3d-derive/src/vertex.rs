@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitInt, Result};
+
+#[derive(Default)]
+struct FieldOptions {
+    skip: bool,
+    normalized: bool,
+    bgra: bool,
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "#[derive(Vertex)] does not support generic structs"));
+    }
+
+    crate::check_repr_c(input, "Vertex")?;
+
+    let divisor = parse_divisor(input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "#[derive(Vertex)] requires a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "#[derive(Vertex)] can only be used on structs")),
+    };
+
+    let mut checks = Vec::new();
+    let mut attribs = Vec::new();
+
+    for field in fields {
+        let options = parse_field_options(field)?;
+        if options.skip {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let normalized = options.normalized;
+        let bgra = options.bgra;
+
+        checks.push(quote! {
+            const _: () = assert!(
+                ::core::mem::offset_of!(#name, #ident) < (1 << 14),
+                concat!("vertex attribute `", stringify!(#ident), "` is placed beyond the maximum attribute offset"),
+            );
+        });
+
+        if normalized {
+            checks.push(quote! {
+                const _: ::deko3d::VtxAttribType = <#ty as ::deko3d::VertexAttrib>::TYPE.normalized();
+            });
+        }
+
+        attribs.push(quote! {
+            ::deko3d::VtxAttribState::for_attrib::<#ty>(
+                buffer_id,
+                ::core::mem::offset_of!(#name, #ident) as u16,
+                #normalized,
+                #bgra,
+            )
+        });
+    }
+
+    let num_attribs = attribs.len();
+
+    Ok(quote! {
+        #(#checks)*
+
+        impl ::deko3d::Vertex for #name {
+            type AttribStates = [::deko3d::VtxAttribState; #num_attribs];
+
+            const DIVISOR: u32 = #divisor;
+
+            fn vtx_attrib_state(buffer_id: u8) -> Self::AttribStates {
+                [#(#attribs),*]
+            }
+        }
+    })
+}
+
+fn parse_divisor(input: &DeriveInput) -> Result<u32> {
+    let mut divisor = 0;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("instance") {
+                divisor = 1;
+                Ok(())
+            } else if meta.path.is_ident("divisor") {
+                divisor = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `instance` or `divisor = N`"))
+            }
+        })?;
+    }
+
+    Ok(divisor)
+}
+
+fn parse_field_options(field: &syn::Field) -> Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else if meta.path.is_ident("normalized") {
+                options.normalized = true;
+            } else if meta.path.is_ident("bgra") {
+                options.bgra = true;
+            } else {
+                return Err(meta.error("expected `skip`, `normalized` or `bgra`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,48 @@
+use std::process::exit;
+
+fn main() {
+    let mut json = false;
+    let mut path = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("usage: dkdisasm [--json] <file>");
+                exit(1);
+            }
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: dkdisasm [--json] <file>");
+            exit(1);
+        }
+    };
+
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            exit(1);
+        }
+    };
+
+    let words = match deko3d_disasm::parse_words(&bytes) {
+        Ok(words) => words,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            exit(1);
+        }
+    };
+
+    let commands = deko3d_disasm::disassemble(&words);
+    if json {
+        print!("{}", deko3d_disasm::to_json(&commands));
+    } else {
+        print!("{}", deko3d_disasm::to_text(&commands));
+    }
+}
@@ -0,0 +1,490 @@
+use std::fmt;
+use std::fmt::Write;
+
+mod methods;
+
+pub use methods::MethodName;
+
+/// Subchannel assignments used by deko3d command buffers.
+pub const SUBCHANNEL_3D: u8 = 0;
+pub const SUBCHANNEL_COMPUTE: u8 = 1;
+pub const SUBCHANNEL_INLINE: u8 = 2;
+pub const SUBCHANNEL_2D: u8 = 3;
+pub const SUBCHANNEL_COPY: u8 = 4;
+
+/// Header of command streams saved by `CapturedCmds::write_to`: the magic, followed by the little-endian
+/// format version and word count.
+pub const CAPTURE_MAGIC: [u8; 4] = *b"DKCC";
+pub const CAPTURE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Engine {
+    ThreeD,
+    Compute,
+    InlineToMemory,
+    TwoD,
+    Copy,
+    Unknown,
+}
+
+impl Engine {
+    pub fn from_subchannel(subchannel: u8) -> Engine {
+        match subchannel {
+            SUBCHANNEL_3D => Engine::ThreeD,
+            SUBCHANNEL_COMPUTE => Engine::Compute,
+            SUBCHANNEL_INLINE => Engine::InlineToMemory,
+            SUBCHANNEL_2D => Engine::TwoD,
+            SUBCHANNEL_COPY => Engine::Copy,
+            _ => Engine::Unknown,
+        }
+    }
+
+    pub fn get_class(self) -> Option<u16> {
+        match self {
+            Engine::ThreeD => Some(0xb197),
+            Engine::Compute => Some(0xb1c0),
+            Engine::InlineToMemory => Some(0xa140),
+            Engine::TwoD => Some(0x902d),
+            Engine::Copy => Some(0xb0b5),
+            Engine::Unknown => None,
+        }
+    }
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Engine::ThreeD => "3D",
+            Engine::Compute => "Compute",
+            Engine::InlineToMemory => "Inline",
+            Engine::TwoD => "2D",
+            Engine::Copy => "Copy",
+            Engine::Unknown => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IncrementMode {
+    /// Each argument goes to the next method.
+    Increasing,
+    /// Every argument goes to the same method.
+    NonIncreasing,
+    /// The first argument goes to the given method, the rest to the one after it.
+    IncreaseOnce,
+    /// A single 13-bit argument stored in the header itself.
+    Immediate,
+    /// Header word with an opcode this decoder doesn't understand.
+    Invalid,
+}
+
+impl IncrementMode {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            IncrementMode::Increasing => "Inc",
+            IncrementMode::NonIncreasing => "NonInc",
+            IncrementMode::IncreaseOnce => "IncOnce",
+            IncrementMode::Immediate => "Imm",
+            IncrementMode::Invalid => "Invalid",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MethodWrite {
+    /// Method offset in bytes.
+    pub method: u16,
+    pub value: u32,
+}
+
+impl MethodWrite {
+    pub fn get_name(&self, engine: Engine) -> Option<MethodName> {
+        methods::lookup(engine, self.method)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// Position of the header in the stream, in words.
+    pub offset: usize,
+    pub header: u32,
+    pub subchannel: u8,
+    pub engine: Engine,
+    pub mode: IncrementMode,
+    /// Method offset in bytes.
+    pub method: u16,
+    pub args: Vec<u32>,
+    /// Set when the header announced more arguments than the stream contains.
+    pub truncated: bool,
+}
+
+impl Command {
+    pub fn writes(&self) -> impl Iterator<Item = MethodWrite> + '_ {
+        self.args.iter().enumerate().map(move |(i, &value)| {
+            let step = match self.mode {
+                IncrementMode::Increasing => i,
+                IncrementMode::IncreaseOnce => i.min(1),
+                _ => 0,
+            };
+
+            MethodWrite { method: self.method.wrapping_add(4 * step as u16), value }
+        })
+    }
+
+    fn method_label(&self, method: u16) -> String {
+        match methods::lookup(self.engine, method) {
+            Some(MethodName { name, index: Some(index) }) => format!("{}({})", name, index),
+            Some(MethodName { name, index: None }) => name.to_string(),
+            None => format!("0x{:04x}", method),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:06x}: {:08x} {:<7} {:<7} {} ",
+            self.offset * 4,
+            self.header,
+            self.engine.get_name(),
+            self.mode.get_name(),
+            self.subchannel
+        )?;
+
+        if self.mode == IncrementMode::Invalid {
+            return Ok(());
+        }
+
+        write!(f, "{} [{}]", self.method_label(self.method), self.args.len())?;
+        if self.truncated {
+            f.write_str(" (truncated)")?;
+        }
+
+        for write in self.writes() {
+            write!(f, "\n    {:<48} = 0x{:08x}", self.method_label(write.method), write.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes a Maxwell push buffer into commands.
+pub fn disassemble(words: &[u32]) -> Vec<Command> {
+    let mut commands = Vec::new();
+    let mut pos = 0;
+
+    while pos < words.len() {
+        let header = words[pos];
+        let subchannel = ((header >> 13) & 0x7) as u8;
+        let method = ((header & 0xfff) << 2) as u16;
+        let count = ((header >> 16) & 0x1fff) as usize;
+
+        let mode = match header >> 29 {
+            1 => IncrementMode::Increasing,
+            3 => IncrementMode::NonIncreasing,
+            4 => IncrementMode::Immediate,
+            5 => IncrementMode::IncreaseOnce,
+            _ => IncrementMode::Invalid,
+        };
+
+        let (args, truncated) = match mode {
+            IncrementMode::Immediate => (vec![count as u32], false),
+            IncrementMode::Invalid => (Vec::new(), false),
+            _ => {
+                let end = (pos + 1 + count).min(words.len());
+                (words[pos + 1..end].to_vec(), end - pos - 1 < count)
+            }
+        };
+
+        let consumed = match mode {
+            IncrementMode::Immediate | IncrementMode::Invalid => 1,
+            _ => 1 + args.len(),
+        };
+
+        commands.push(Command {
+            offset: pos,
+            header,
+            subchannel,
+            engine: Engine::from_subchannel(subchannel),
+            mode,
+            method,
+            args,
+            truncated,
+        });
+
+        pos += consumed;
+    }
+
+    commands
+}
+
+/// Formats commands as human readable text, one command header per line.
+pub fn to_text(commands: &[Command]) -> String {
+    let mut out = String::new();
+    for cmd in commands {
+        writeln!(out, "{}", cmd).unwrap();
+    }
+    out
+}
+
+/// Formats commands as a JSON array.
+pub fn to_json(commands: &[Command]) -> String {
+    let mut out = String::from("[");
+
+    for (i, cmd) in commands.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+
+        write!(
+            out,
+            "\n  {{\"offset\":{},\"header\":{},\"subchannel\":{},\"engine\":\"{}\",",
+            cmd.offset * 4,
+            cmd.header,
+            cmd.subchannel,
+            cmd.engine.get_name()
+        )
+        .unwrap();
+        match cmd.engine.get_class() {
+            Some(class) => write!(out, "\"class\":{},", class).unwrap(),
+            None => out.push_str("\"class\":null,"),
+        }
+        write!(
+            out,
+            "\"mode\":\"{}\",\"method\":{},\"truncated\":{},\"writes\":[",
+            cmd.mode.get_name(),
+            cmd.method,
+            cmd.truncated
+        )
+        .unwrap();
+
+        for (j, write) in cmd.writes().enumerate() {
+            if j != 0 {
+                out.push(',');
+            }
+
+            write!(out, "{{\"method\":{},", write.method).unwrap();
+            match write.get_name(cmd.engine) {
+                Some(MethodName { name, index }) => {
+                    write!(out, "\"name\":\"{}\",", name).unwrap();
+                    match index {
+                        Some(index) => write!(out, "\"index\":{},", index).unwrap(),
+                        None => out.push_str("\"index\":null,"),
+                    }
+                }
+                None => out.push_str("\"name\":null,\"index\":null,"),
+            }
+            write!(out, "\"value\":{}}}", write.value).unwrap();
+        }
+
+        out.push_str("]}");
+    }
+
+    out.push_str("\n]\n");
+    out
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ParseError {
+    /// The file starts with [`CAPTURE_MAGIC`] but ends inside the header.
+    TruncatedHeader,
+    UnsupportedVersion(u32),
+    /// The header announced a different amount of command data than the file contains, in bytes.
+    LengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TruncatedHeader => f.write_str("truncated captured command stream header"),
+            ParseError::UnsupportedVersion(version) => {
+                write!(f, "unsupported captured command stream version {}", version)
+            }
+            ParseError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} bytes of captured commands, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Reads words from a file produced by `CapturedCmds::write_to`, or from a headerless
+/// little-endian dump of command memory.
+///
+/// Saved captures are validated the same way `CapturedCmds::read_from` does: the version must be
+/// [`CAPTURE_FORMAT_VERSION`] and the word count must match the rest of the file.
+pub fn parse_words(bytes: &[u8]) -> Result<Vec<u32>, ParseError> {
+    let body = match bytes.strip_prefix(&CAPTURE_MAGIC) {
+        Some(rest) => {
+            if rest.len() < 8 {
+                return Err(ParseError::TruncatedHeader);
+            }
+
+            let version = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            if version != CAPTURE_FORMAT_VERSION {
+                return Err(ParseError::UnsupportedVersion(version));
+            }
+
+            let len = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+            let body = &rest[8..];
+            if body.len() != len * 4 {
+                return Err(ParseError::LengthMismatch { expected: len * 4, actual: body.len() });
+            }
+
+            body
+        }
+        None => bytes,
+    };
+
+    Ok(body.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(mode: u32, count: u32, subchannel: u8, method: u16) -> u32 {
+        (mode << 29) | (count << 16) | ((subchannel as u32) << 13) | (method as u32 >> 2)
+    }
+
+    fn saved(version: u32, len: u32, words: &[u32]) -> Vec<u8> {
+        let mut bytes = CAPTURE_MAGIC.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn methods(cmd: &Command) -> Vec<u16> {
+        cmd.writes().map(|write| write.method).collect()
+    }
+
+    #[test]
+    fn increasing() {
+        let cmds = disassemble(&[header(1, 3, SUBCHANNEL_3D, 0x0d80), 1, 2, 3]);
+
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].mode, IncrementMode::Increasing);
+        assert_eq!(cmds[0].engine, Engine::ThreeD);
+        assert_eq!(cmds[0].args, [1, 2, 3]);
+        assert!(!cmds[0].truncated);
+        assert_eq!(methods(&cmds[0]), [0x0d80, 0x0d84, 0x0d88]);
+
+        let names: Vec<_> = cmds[0].writes().map(|write| write.get_name(Engine::ThreeD).unwrap()).collect();
+        assert_eq!(names[2], MethodName { name: "SET_COLOR_CLEAR_VALUE", index: Some(2) });
+    }
+
+    #[test]
+    fn non_increasing() {
+        let cmds = disassemble(&[header(3, 3, SUBCHANNEL_INLINE, 0x01b4), 7, 8, 9]);
+
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].mode, IncrementMode::NonIncreasing);
+        assert_eq!(cmds[0].engine, Engine::InlineToMemory);
+        assert_eq!(cmds[0].args, [7, 8, 9]);
+        assert_eq!(methods(&cmds[0]), [0x01b4; 3]);
+    }
+
+    #[test]
+    fn increase_once() {
+        let cmds = disassemble(&[header(5, 3, SUBCHANNEL_3D, 0x1234), 0, 3, 6]);
+
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].mode, IncrementMode::IncreaseOnce);
+        assert_eq!(methods(&cmds[0]), [0x1234, 0x1238, 0x1238]);
+    }
+
+    #[test]
+    fn immediate() {
+        let cmds = disassemble(&[header(4, 0x1abc, SUBCHANNEL_COPY, 0x0300), header(4, 1, SUBCHANNEL_3D, 0x1614)]);
+
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].mode, IncrementMode::Immediate);
+        assert_eq!(cmds[0].engine, Engine::Copy);
+        assert_eq!(cmds[0].args, [0x1abc]);
+        assert_eq!(methods(&cmds[0]), [0x0300]);
+        assert_eq!(cmds[1].offset, 1);
+        assert_eq!(cmds[1].args, [1]);
+    }
+
+    #[test]
+    fn invalid() {
+        let cmds = disassemble(&[0x0000_0001, header(4, 0, SUBCHANNEL_3D, 0x0100)]);
+
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].mode, IncrementMode::Invalid);
+        assert!(cmds[0].args.is_empty());
+        assert_eq!(cmds[0].writes().count(), 0);
+        assert_eq!(cmds[0].to_string(), "000000: 00000001 3D      Invalid 0 ");
+        assert_eq!(cmds[1].offset, 1);
+    }
+
+    #[test]
+    fn truncated() {
+        let cmds = disassemble(&[header(1, 4, SUBCHANNEL_3D, 0x0d80), 1, 2]);
+
+        assert_eq!(cmds.len(), 1);
+        assert!(cmds[0].truncated);
+        assert_eq!(cmds[0].args, [1, 2]);
+        assert!(cmds[0].to_string().contains("SET_COLOR_CLEAR_VALUE(0) [2] (truncated)"));
+    }
+
+    #[test]
+    fn host_methods_on_any_subchannel() {
+        let cmds = disassemble(&[header(4, 0, 7, 0x0078)]);
+
+        assert_eq!(cmds[0].engine, Engine::Unknown);
+        assert_eq!(cmds[0].engine.get_class(), None);
+        assert_eq!(cmds[0].writes().next().unwrap().get_name(Engine::Unknown).unwrap().name, "WFI");
+    }
+
+    #[test]
+    fn unknown_method_label() {
+        let text = disassemble(&[header(4, 5, SUBCHANNEL_2D, 0x0ffc)])[0].to_string();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "000000: 800563ff 2D      Imm     3 0x0ffc [1]");
+        assert_eq!(lines[1].split_whitespace().collect::<Vec<_>>(), ["0x0ffc", "=", "0x00000005"]);
+    }
+
+    #[test]
+    fn parse_headerless() {
+        assert_eq!(parse_words(&[1, 0, 0, 0, 2, 0, 0, 0]), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn parse_saved() {
+        assert_eq!(parse_words(&saved(CAPTURE_FORMAT_VERSION, 2, &[3, 4])), Ok(vec![3, 4]));
+        assert_eq!(parse_words(&saved(CAPTURE_FORMAT_VERSION, 0, &[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_truncated_header() {
+        assert_eq!(parse_words(b"DKCC\x01\x00\x00\x00"), Err(ParseError::TruncatedHeader));
+    }
+
+    #[test]
+    fn parse_unsupported_version() {
+        assert_eq!(
+            parse_words(&saved(CAPTURE_FORMAT_VERSION + 1, 1, &[0])),
+            Err(ParseError::UnsupportedVersion(CAPTURE_FORMAT_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn parse_length_mismatch() {
+        assert_eq!(
+            parse_words(&saved(CAPTURE_FORMAT_VERSION, 3, &[0, 0])),
+            Err(ParseError::LengthMismatch { expected: 12, actual: 8 })
+        );
+        assert_eq!(
+            parse_words(&saved(CAPTURE_FORMAT_VERSION, 1, &[0, 0])),
+            Err(ParseError::LengthMismatch { expected: 4, actual: 8 })
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::Engine;
+
+// (first method, count, stride, name), all offsets in bytes.
+type MethodTable = &'static [(u16, u16, u16, &'static str)];
+
+static HOST_METHODS: MethodTable = &[
+    (0x0000, 1, 0, "SET_OBJECT"),
+    (0x0004, 1, 0, "ILLEGAL"),
+    (0x0008, 1, 0, "NOP"),
+    (0x0010, 1, 0, "SEMAPHOREA"),
+    (0x0014, 1, 0, "SEMAPHOREB"),
+    (0x0018, 1, 0, "SEMAPHOREC"),
+    (0x001c, 1, 0, "SEMAPHORED"),
+    (0x0020, 1, 0, "NON_STALL_INTERRUPT"),
+    (0x0024, 1, 0, "FB_FLUSH"),
+    (0x0030, 1, 0, "MEM_OP_C"),
+    (0x0034, 1, 0, "MEM_OP_D"),
+    (0x0050, 1, 0, "SET_REFERENCE"),
+    (0x0078, 1, 0, "WFI"),
+    (0x007c, 1, 0, "CRC_CHECK"),
+    (0x0080, 1, 0, "YIELD"),
+];
+
+// Methods shared by the 3D, compute and inline-to-memory classes.
+static COMMON_METHODS: MethodTable = &[
+    (0x0100, 1, 0, "NO_OPERATION"),
+    (0x0104, 1, 0, "SET_NOTIFY_A"),
+    (0x0108, 1, 0, "SET_NOTIFY_B"),
+    (0x010c, 1, 0, "NOTIFY"),
+    (0x0110, 1, 0, "WAIT_FOR_IDLE"),
+    (0x0180, 1, 0, "LINE_LENGTH_IN"),
+    (0x0184, 1, 0, "LINE_COUNT"),
+    (0x0188, 1, 0, "OFFSET_OUT_UPPER"),
+    (0x018c, 1, 0, "OFFSET_OUT"),
+    (0x0190, 1, 0, "PITCH_OUT"),
+    (0x0194, 1, 0, "SET_DST_BLOCK_SIZE"),
+    (0x0198, 1, 0, "SET_DST_WIDTH"),
+    (0x019c, 1, 0, "SET_DST_HEIGHT"),
+    (0x01a0, 1, 0, "SET_DST_DEPTH"),
+    (0x01a4, 1, 0, "SET_DST_LAYER"),
+    (0x01a8, 1, 0, "SET_DST_ORIGIN_BYTES_X"),
+    (0x01ac, 1, 0, "SET_DST_ORIGIN_SAMPLES_Y"),
+    (0x01b0, 1, 0, "LAUNCH_DMA"),
+    (0x01b4, 1, 0, "LOAD_INLINE_DATA"),
+];
+
+static THREED_METHODS: MethodTable = &[
+    (0x0114, 1, 0, "LOAD_MME_INSTRUCTION_RAM_POINTER"),
+    (0x0118, 1, 0, "LOAD_MME_INSTRUCTION_RAM"),
+    (0x011c, 1, 0, "LOAD_MME_START_ADDRESS_RAM_POINTER"),
+    (0x0120, 1, 0, "LOAD_MME_START_ADDRESS_RAM"),
+    (0x0124, 1, 0, "SET_MME_SHADOW_RAM_CONTROL"),
+    (0x0800, 8, 0x40, "SET_COLOR_TARGET_A"),
+    (0x0804, 8, 0x40, "SET_COLOR_TARGET_B"),
+    (0x0808, 8, 0x40, "SET_COLOR_TARGET_WIDTH"),
+    (0x080c, 8, 0x40, "SET_COLOR_TARGET_HEIGHT"),
+    (0x0810, 8, 0x40, "SET_COLOR_TARGET_FORMAT"),
+    (0x0814, 8, 0x40, "SET_COLOR_TARGET_MEMORY"),
+    (0x0818, 8, 0x40, "SET_COLOR_TARGET_THIRD_DIMENSION"),
+    (0x081c, 8, 0x40, "SET_COLOR_TARGET_ARRAY_PITCH"),
+    (0x0820, 8, 0x40, "SET_COLOR_TARGET_LAYER"),
+    (0x0824, 8, 0x40, "SET_COLOR_TARGET_MARK"),
+    (0x0a00, 16, 0x20, "SET_VIEWPORT_SCALE_X"),
+    (0x0a04, 16, 0x20, "SET_VIEWPORT_SCALE_Y"),
+    (0x0a08, 16, 0x20, "SET_VIEWPORT_SCALE_Z"),
+    (0x0a0c, 16, 0x20, "SET_VIEWPORT_OFFSET_X"),
+    (0x0a10, 16, 0x20, "SET_VIEWPORT_OFFSET_Y"),
+    (0x0a14, 16, 0x20, "SET_VIEWPORT_OFFSET_Z"),
+    (0x0a18, 16, 0x20, "SET_VIEWPORT_COORDINATE_SWIZZLE"),
+    (0x0a1c, 16, 0x20, "SET_VIEWPORT_INCREASE_SNAP_GRID_PRECISION"),
+    (0x0c00, 16, 0x10, "SET_VIEWPORT_CLIP_HORIZONTAL"),
+    (0x0c04, 16, 0x10, "SET_VIEWPORT_CLIP_VERTICAL"),
+    (0x0c08, 16, 0x10, "SET_VIEWPORT_CLIP_MIN_Z"),
+    (0x0c0c, 16, 0x10, "SET_VIEWPORT_CLIP_MAX_Z"),
+    (0x0d80, 4, 0x04, "SET_COLOR_CLEAR_VALUE"),
+    (0x0d90, 1, 0, "SET_Z_CLEAR_VALUE"),
+    (0x0da0, 1, 0, "SET_STENCIL_CLEAR_VALUE"),
+    (0x0e00, 16, 0x10, "SET_SCISSOR_ENABLE"),
+    (0x0e04, 16, 0x10, "SET_SCISSOR_HORIZONTAL"),
+    (0x0e08, 16, 0x10, "SET_SCISSOR_VERTICAL"),
+    (0x0fe0, 1, 0, "SET_ZT_A"),
+    (0x0fe4, 1, 0, "SET_ZT_B"),
+    (0x0fe8, 1, 0, "SET_ZT_FORMAT"),
+    (0x0fec, 1, 0, "SET_ZT_BLOCK_SIZE"),
+    (0x0ff0, 1, 0, "SET_ZT_ARRAY_PITCH"),
+    (0x0ff4, 1, 0, "SET_SURFACE_CLIP_HORIZONTAL"),
+    (0x0ff8, 1, 0, "SET_SURFACE_CLIP_VERTICAL"),
+    (0x1160, 32, 0x04, "SET_VERTEX_ATTRIBUTE_A"),
+    (0x121c, 1, 0, "SET_CT_SELECT"),
+    (0x1228, 1, 0, "SET_ZT_SIZE_A"),
+    (0x122c, 1, 0, "SET_ZT_SIZE_B"),
+    (0x1230, 1, 0, "SET_ZT_SIZE_C"),
+    (0x1234, 1, 0, "SET_VERTEX_ARRAY_START"),
+    (0x1238, 1, 0, "DRAW_VERTEX_ARRAY"),
+    (0x12cc, 1, 0, "SET_DEPTH_TEST"),
+    (0x12e4, 1, 0, "SET_BLEND_STATE_PER_TARGET"),
+    (0x12e8, 1, 0, "SET_DEPTH_WRITE"),
+    (0x12ec, 1, 0, "SET_ALPHA_TEST"),
+    (0x130c, 1, 0, "SET_DEPTH_FUNC"),
+    (0x1310, 1, 0, "SET_ALPHA_REF"),
+    (0x1314, 1, 0, "SET_ALPHA_FUNC"),
+    (0x131c, 1, 0, "SET_BLEND_CONST_RED"),
+    (0x1320, 1, 0, "SET_BLEND_CONST_GREEN"),
+    (0x1324, 1, 0, "SET_BLEND_CONST_BLUE"),
+    (0x1328, 1, 0, "SET_BLEND_CONST_ALPHA"),
+    (0x1340, 1, 0, "SET_BLEND_COLOR_OP"),
+    (0x1344, 1, 0, "SET_BLEND_COLOR_SOURCE_COEFF"),
+    (0x1348, 1, 0, "SET_BLEND_COLOR_DEST_COEFF"),
+    (0x134c, 1, 0, "SET_BLEND_ALPHA_OP"),
+    (0x1350, 1, 0, "SET_BLEND_ALPHA_SOURCE_COEFF"),
+    (0x1358, 1, 0, "SET_BLEND_ALPHA_DEST_COEFF"),
+    (0x1360, 8, 0x04, "SET_BLEND"),
+    (0x1380, 1, 0, "SET_STENCIL_TEST"),
+    (0x1384, 1, 0, "SET_STENCIL_OP_FAIL"),
+    (0x1388, 1, 0, "SET_STENCIL_OP_ZFAIL"),
+    (0x138c, 1, 0, "SET_STENCIL_OP_ZPASS"),
+    (0x1390, 1, 0, "SET_STENCIL_FUNC"),
+    (0x1394, 1, 0, "SET_STENCIL_FUNC_REF"),
+    (0x1398, 1, 0, "SET_STENCIL_FUNC_MASK"),
+    (0x139c, 1, 0, "SET_STENCIL_MASK"),
+    (0x155c, 1, 0, "SET_TEX_SAMPLER_POOL_A"),
+    (0x1560, 1, 0, "SET_TEX_SAMPLER_POOL_B"),
+    (0x1564, 1, 0, "SET_TEX_SAMPLER_POOL_C"),
+    (0x1574, 1, 0, "SET_TEX_HEADER_POOL_A"),
+    (0x1578, 1, 0, "SET_TEX_HEADER_POOL_B"),
+    (0x157c, 1, 0, "SET_TEX_HEADER_POOL_C"),
+    (0x1608, 1, 0, "SET_PROGRAM_REGION_A"),
+    (0x160c, 1, 0, "SET_PROGRAM_REGION_B"),
+    (0x1614, 1, 0, "END"),
+    (0x1618, 1, 0, "BEGIN"),
+    (0x17c8, 1, 0, "SET_INDEX_BUFFER_A"),
+    (0x17cc, 1, 0, "SET_INDEX_BUFFER_B"),
+    (0x17d0, 1, 0, "SET_INDEX_BUFFER_C"),
+    (0x17d4, 1, 0, "SET_INDEX_BUFFER_D"),
+    (0x17d8, 1, 0, "SET_INDEX_BUFFER_E"),
+    (0x17dc, 1, 0, "SET_INDEX_BUFFER_F"),
+    (0x17e0, 1, 0, "DRAW_INDEX_BUFFER"),
+    (0x1918, 1, 0, "OGL_SET_CULL"),
+    (0x191c, 1, 0, "OGL_SET_FRONT_FACE"),
+    (0x1920, 1, 0, "OGL_SET_CULL_FACE"),
+    (0x19d0, 1, 0, "CLEAR_SURFACE"),
+    (0x1b00, 1, 0, "SET_REPORT_SEMAPHORE_A"),
+    (0x1b04, 1, 0, "SET_REPORT_SEMAPHORE_B"),
+    (0x1b08, 1, 0, "SET_REPORT_SEMAPHORE_C"),
+    (0x1b0c, 1, 0, "SET_REPORT_SEMAPHORE_D"),
+    (0x1c00, 32, 0x10, "SET_VERTEX_STREAM_A_FORMAT"),
+    (0x1c04, 32, 0x10, "SET_VERTEX_STREAM_A_LOCATION_A"),
+    (0x1c08, 32, 0x10, "SET_VERTEX_STREAM_A_LOCATION_B"),
+    (0x1c0c, 32, 0x10, "SET_VERTEX_STREAM_A_FREQUENCY"),
+    (0x1e00, 8, 0x20, "SET_BLEND_PER_TARGET_SEPARATE_FOR_ALPHA"),
+    (0x1e04, 8, 0x20, "SET_BLEND_PER_TARGET_COLOR_OP"),
+    (0x1e08, 8, 0x20, "SET_BLEND_PER_TARGET_COLOR_SOURCE_COEFF"),
+    (0x1e0c, 8, 0x20, "SET_BLEND_PER_TARGET_COLOR_DEST_COEFF"),
+    (0x1e10, 8, 0x20, "SET_BLEND_PER_TARGET_ALPHA_OP"),
+    (0x1e14, 8, 0x20, "SET_BLEND_PER_TARGET_ALPHA_SOURCE_COEFF"),
+    (0x1e18, 8, 0x20, "SET_BLEND_PER_TARGET_ALPHA_DEST_COEFF"),
+    (0x1f00, 32, 0x08, "SET_VERTEX_STREAM_LIMIT_A"),
+    (0x1f04, 32, 0x08, "SET_VERTEX_STREAM_LIMIT_B"),
+    (0x2000, 6, 0x40, "SET_PIPELINE_SHADER"),
+    (0x2004, 6, 0x40, "SET_PIPELINE_PROGRAM"),
+    (0x200c, 6, 0x40, "SET_PIPELINE_REGISTER_COUNT"),
+    (0x2010, 6, 0x40, "SET_PIPELINE_BINDING"),
+    (0x2380, 1, 0, "SET_CONSTANT_BUFFER_SELECTOR_A"),
+    (0x2384, 1, 0, "SET_CONSTANT_BUFFER_SELECTOR_B"),
+    (0x2388, 1, 0, "SET_CONSTANT_BUFFER_SELECTOR_C"),
+    (0x238c, 1, 0, "LOAD_CONSTANT_BUFFER_OFFSET"),
+    (0x2390, 16, 0x04, "LOAD_CONSTANT_BUFFER"),
+    (0x2400, 5, 0x20, "BIND_GROUP_RESERVED_A"),
+    (0x2404, 5, 0x20, "BIND_GROUP_RESERVED_B"),
+    (0x2408, 5, 0x20, "BIND_GROUP_RESERVED_C"),
+    (0x240c, 5, 0x20, "BIND_GROUP_RESERVED_D"),
+    (0x2410, 5, 0x20, "BIND_GROUP_CONSTANT_BUFFER"),
+    (0x3800, 128, 0x08, "CALL_MME_MACRO"),
+    (0x3804, 128, 0x08, "CALL_MME_DATA"),
+];
+
+static COMPUTE_METHODS: MethodTable = &[
+    (0x02b4, 1, 0, "SEND_PCAS_A"),
+    (0x02b8, 1, 0, "SEND_PCAS_B"),
+    (0x02bc, 1, 0, "SEND_SIGNALING_PCAS_B"),
+    (0x077c, 1, 0, "SET_SHADER_SHARED_MEMORY_WINDOW"),
+    (0x0790, 1, 0, "SET_SHADER_LOCAL_MEMORY_A"),
+    (0x0794, 1, 0, "SET_SHADER_LOCAL_MEMORY_B"),
+    (0x07b0, 1, 0, "SET_SHADER_LOCAL_MEMORY_WINDOW"),
+    (0x155c, 1, 0, "SET_TEX_SAMPLER_POOL_A"),
+    (0x1560, 1, 0, "SET_TEX_SAMPLER_POOL_B"),
+    (0x1564, 1, 0, "SET_TEX_SAMPLER_POOL_C"),
+    (0x1574, 1, 0, "SET_TEX_HEADER_POOL_A"),
+    (0x1578, 1, 0, "SET_TEX_HEADER_POOL_B"),
+    (0x157c, 1, 0, "SET_TEX_HEADER_POOL_C"),
+    (0x1608, 1, 0, "SET_PROGRAM_REGION_A"),
+    (0x160c, 1, 0, "SET_PROGRAM_REGION_B"),
+    (0x1b00, 1, 0, "SET_REPORT_SEMAPHORE_A"),
+    (0x1b04, 1, 0, "SET_REPORT_SEMAPHORE_B"),
+    (0x1b08, 1, 0, "SET_REPORT_SEMAPHORE_C"),
+    (0x1b0c, 1, 0, "SET_REPORT_SEMAPHORE_D"),
+];
+
+static TWOD_METHODS: MethodTable = &[
+    (0x0100, 1, 0, "NO_OPERATION"),
+    (0x0110, 1, 0, "WAIT_FOR_IDLE"),
+    (0x0200, 1, 0, "SET_DST_FORMAT"),
+    (0x0204, 1, 0, "SET_DST_MEMORY_LAYOUT"),
+    (0x0208, 1, 0, "SET_DST_BLOCK_SIZE"),
+    (0x020c, 1, 0, "SET_DST_DEPTH"),
+    (0x0210, 1, 0, "SET_DST_LAYER"),
+    (0x0214, 1, 0, "SET_DST_PITCH"),
+    (0x0218, 1, 0, "SET_DST_WIDTH"),
+    (0x021c, 1, 0, "SET_DST_HEIGHT"),
+    (0x0220, 1, 0, "SET_DST_OFFSET_UPPER"),
+    (0x0224, 1, 0, "SET_DST_OFFSET_LOWER"),
+    (0x0230, 1, 0, "SET_SRC_FORMAT"),
+    (0x0234, 1, 0, "SET_SRC_MEMORY_LAYOUT"),
+    (0x0238, 1, 0, "SET_SRC_BLOCK_SIZE"),
+    (0x023c, 1, 0, "SET_SRC_DEPTH"),
+    (0x0240, 1, 0, "SET_SRC_LAYER"),
+    (0x0244, 1, 0, "SET_SRC_PITCH"),
+    (0x0248, 1, 0, "SET_SRC_WIDTH"),
+    (0x024c, 1, 0, "SET_SRC_HEIGHT"),
+    (0x0250, 1, 0, "SET_SRC_OFFSET_UPPER"),
+    (0x0254, 1, 0, "SET_SRC_OFFSET_LOWER"),
+    (0x0280, 1, 0, "SET_CLIP_X0"),
+    (0x0284, 1, 0, "SET_CLIP_Y0"),
+    (0x0288, 1, 0, "SET_CLIP_WIDTH"),
+    (0x028c, 1, 0, "SET_CLIP_HEIGHT"),
+    (0x0290, 1, 0, "SET_CLIP_ENABLE"),
+    (0x0294, 1, 0, "SET_COLOR_KEY_FORMAT"),
+    (0x0298, 1, 0, "SET_COLOR_KEY"),
+    (0x029c, 1, 0, "SET_COLOR_KEY_ENABLE"),
+    (0x02a0, 1, 0, "SET_ROP"),
+    (0x02a4, 1, 0, "SET_BETA1"),
+    (0x02a8, 1, 0, "SET_BETA4"),
+    (0x02ac, 1, 0, "SET_OPERATION"),
+    (0x0888, 1, 0, "SET_PIXELS_FROM_MEMORY_SAMPLE_MODE"),
+    (0x088c, 1, 0, "SET_PIXELS_FROM_MEMORY_DST_X0"),
+    (0x0890, 1, 0, "SET_PIXELS_FROM_MEMORY_DST_Y0"),
+    (0x0894, 1, 0, "SET_PIXELS_FROM_MEMORY_DST_WIDTH"),
+    (0x0898, 1, 0, "SET_PIXELS_FROM_MEMORY_DST_HEIGHT"),
+    (0x089c, 1, 0, "SET_PIXELS_FROM_MEMORY_DU_DX_FRAC"),
+    (0x08a0, 1, 0, "SET_PIXELS_FROM_MEMORY_DU_DX_INT"),
+    (0x08a4, 1, 0, "SET_PIXELS_FROM_MEMORY_DV_DY_FRAC"),
+    (0x08a8, 1, 0, "SET_PIXELS_FROM_MEMORY_DV_DY_INT"),
+    (0x08ac, 1, 0, "SET_PIXELS_FROM_MEMORY_SRC_X0_FRAC"),
+    (0x08b0, 1, 0, "SET_PIXELS_FROM_MEMORY_SRC_X0_INT"),
+    (0x08b4, 1, 0, "SET_PIXELS_FROM_MEMORY_SRC_Y0_FRAC"),
+    (0x08b8, 1, 0, "PIXELS_FROM_MEMORY_SRC_Y0_INT"),
+];
+
+static COPY_METHODS: MethodTable = &[
+    (0x0100, 1, 0, "NOP"),
+    (0x0140, 1, 0, "PM_TRIGGER"),
+    (0x0240, 1, 0, "SET_SEMAPHORE_A"),
+    (0x0244, 1, 0, "SET_SEMAPHORE_B"),
+    (0x0248, 1, 0, "SET_SEMAPHORE_PAYLOAD"),
+    (0x0300, 1, 0, "LAUNCH_DMA"),
+    (0x0400, 1, 0, "OFFSET_IN_UPPER"),
+    (0x0404, 1, 0, "OFFSET_IN_LOWER"),
+    (0x0408, 1, 0, "OFFSET_OUT_UPPER"),
+    (0x040c, 1, 0, "OFFSET_OUT_LOWER"),
+    (0x0410, 1, 0, "PITCH_IN"),
+    (0x0414, 1, 0, "PITCH_OUT"),
+    (0x0418, 1, 0, "LINE_LENGTH_IN"),
+    (0x041c, 1, 0, "LINE_COUNT"),
+    (0x0700, 1, 0, "SET_REMAP_CONST_A"),
+    (0x0704, 1, 0, "SET_REMAP_CONST_B"),
+    (0x0708, 1, 0, "SET_REMAP_COMPONENTS"),
+    (0x070c, 1, 0, "SET_DST_BLOCK_SIZE"),
+    (0x0710, 1, 0, "SET_DST_WIDTH"),
+    (0x0714, 1, 0, "SET_DST_HEIGHT"),
+    (0x0718, 1, 0, "SET_DST_DEPTH"),
+    (0x071c, 1, 0, "SET_DST_LAYER"),
+    (0x0720, 1, 0, "SET_DST_ORIGIN"),
+    (0x0728, 1, 0, "SET_SRC_BLOCK_SIZE"),
+    (0x072c, 1, 0, "SET_SRC_WIDTH"),
+    (0x0730, 1, 0, "SET_SRC_HEIGHT"),
+    (0x0734, 1, 0, "SET_SRC_DEPTH"),
+    (0x0738, 1, 0, "SET_SRC_LAYER"),
+    (0x073c, 1, 0, "SET_SRC_ORIGIN"),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MethodName {
+    pub name: &'static str,
+    pub index: Option<u16>,
+}
+
+pub fn lookup(engine: Engine, method: u16) -> Option<MethodName> {
+    // Methods below 0x100 are consumed by the host interface regardless of the subchannel.
+    let tables: &[MethodTable] = match engine {
+        _ if method < 0x100 => &[HOST_METHODS],
+        Engine::ThreeD => &[COMMON_METHODS, THREED_METHODS],
+        Engine::Compute => &[COMMON_METHODS, COMPUTE_METHODS],
+        Engine::InlineToMemory => &[COMMON_METHODS],
+        Engine::TwoD => &[TWOD_METHODS],
+        Engine::Copy => &[COPY_METHODS],
+        Engine::Unknown => &[],
+    };
+
+    for &(base, count, stride, name) in tables.iter().flat_map(|table| table.iter()) {
+        if method < base {
+            continue;
+        }
+
+        let delta = method - base;
+        if count == 1 {
+            if delta == 0 {
+                return Some(MethodName { name, index: None });
+            }
+        } else if delta.is_multiple_of(stride) && delta / stride < count {
+            return Some(MethodName { name, index: Some(delta / stride) });
+        }
+    }
+
+    None
+}
@@ -0,0 +1,20 @@
+//! Disassembles a saved capture of a small frame and compares it against the checked-in dumps.
+//!
+//! After an intended change to the output format, regenerate the dumps with
+//! `cargo run --bin dkdisasm [--json] tests/data/frame.dkcc`.
+
+use deko3d_disasm::{disassemble, parse_words, to_json, to_text};
+
+const FRAME: &[u8] = include_bytes!("data/frame.dkcc");
+
+#[test]
+fn frame_text() {
+    let commands = disassemble(&parse_words(FRAME).unwrap());
+    assert_eq!(to_text(&commands), include_str!("data/frame.txt"));
+}
+
+#[test]
+fn frame_json() {
+    let commands = disassemble(&parse_words(FRAME).unwrap());
+    assert_eq!(to_json(&commands), include_str!("data/frame.json"));
+}
@@ -0,0 +1,67 @@
+/// An IEEE 754 half-precision float, as stored in 16-bit float vertex attributes and images.
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Half(pub u16);
+
+impl Half {
+    pub const fn from_bits(bits: u16) -> Self {
+        Half(bits)
+    }
+
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_f32(value: f32) -> Self {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exp = ((bits >> 23) & 0xff) as i32;
+        let mant = bits & 0x7f_ffff;
+
+        if exp == 0xff {
+            let nan = if mant != 0 { 0x200 } else { 0 };
+            return Half(sign | 0x7c00 | nan);
+        }
+
+        let exp = exp - 127 + 15;
+        if exp >= 0x1f {
+            return Half(sign | 0x7c00);
+        }
+
+        if exp <= 0 {
+            if exp < -10 {
+                return Half(sign);
+            }
+
+            let mant = mant | 0x80_0000;
+            let shift = (14 - exp) as u32;
+            let half = mant >> shift;
+            let rem = mant & ((1 << shift) - 1);
+            let halfway = 1 << (shift - 1);
+            let round = rem > halfway || (rem == halfway && (half & 1) != 0);
+
+            return Half(sign | (half + round as u32) as u16);
+        }
+
+        let half = ((exp as u32) << 10) | (mant >> 13);
+        let rem = mant & 0x1fff;
+        let round = rem > 0x1000 || (rem == 0x1000 && (half & 1) != 0);
+
+        Half(sign | (half + round as u32) as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exp = ((self.0 >> 10) & 0x1f) as u32;
+        let mant = (self.0 & 0x3ff) as u32;
+
+        match exp {
+            0 => {
+                let value = mant as f32 * (1.0 / (1 << 24) as f32);
+                f32::from_bits(sign | value.to_bits())
+            }
+            0x1f => f32::from_bits(sign | 0x7f80_0000 | (mant << 13)),
+            _ => f32::from_bits(sign | ((exp + 112) << 23) | (mant << 13)),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+
+mod half;
+
+pub use half::Half;
+
+/// Pixel formats [`HostImage::decode`] can convert, named after the deko3d image formats they
+/// correspond to. sRGB and depth formats share the layout of their linear and color counterparts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HostFormat {
+    R8Unorm,
+    RG8Unorm,
+    RGBA8Unorm,
+    RGBX8Unorm,
+    BGRA8Unorm,
+    BGRX8Unorm,
+    R16Float,
+    RG16Float,
+    RGBA16Float,
+    RGBX16Float,
+    R16Unorm,
+    RGBA16Unorm,
+    RGBX16Unorm,
+    R32Float,
+    RG32Float,
+    RGBA32Float,
+    RGBX32Float,
+    RGB10A2Unorm,
+    RG11B10Float,
+}
+
+impl HostFormat {
+    pub fn bytes_per_pixel(self) -> u32 {
+        use HostFormat::*;
+
+        match self {
+            R8Unorm => 1,
+            RG8Unorm | R16Float | R16Unorm => 2,
+            RGBA8Unorm | RGBX8Unorm | BGRA8Unorm | BGRX8Unorm | RG16Float | R32Float | RGB10A2Unorm | RG11B10Float => 4,
+            RGBA16Float | RGBX16Float | RGBA16Unorm | RGBX16Unorm | RG32Float => 8,
+            RGBA32Float | RGBX32Float => 16,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The data is shorter than the image it should hold, in bytes.
+    Truncated { expected: usize, actual: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated { expected, actual } => {
+                write!(f, "expected {} bytes of pixel data, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Pixels of a [`HostImage`], four components per pixel in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostPixels {
+    Rgba8(Vec<u8>),
+    Rgba32Float(Vec<f32>),
+}
+
+/// An image decoded into host memory.
+///
+/// 8-bit normalized formats (including sRGB and BGR orderings) decode to [`HostPixels::Rgba8`] with
+/// their encoded values untouched; everything else decodes to [`HostPixels::Rgba32Float`]. Missing
+/// components read as 0, missing alpha as 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostImage {
+    width: u32,
+    height: u32,
+    pixels: HostPixels,
+}
+
+fn unpack_small_float(bits: u32, mant_bits: u32) -> f32 {
+    let exp = (bits >> mant_bits) & 0x1f;
+    let mant = bits & ((1 << mant_bits) - 1);
+    let scale = (1 << mant_bits) as f32;
+
+    match exp {
+        0 => mant as f32 / scale * 2f32.powi(-14),
+        0x1f if mant == 0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mant as f32 / scale) * 2f32.powi(exp as i32 - 15),
+    }
+}
+
+impl HostImage {
+    /// Decodes tightly packed pixels of the given format.
+    pub fn decode(format: HostFormat, width: u32, height: u32, data: &[u8]) -> Result<Self, DecodeError> {
+        use HostFormat::*;
+
+        let bpp = format.bytes_per_pixel() as usize;
+        let num_pixels = width as usize * height as usize;
+        if data.len() < num_pixels * bpp {
+            return Err(DecodeError::Truncated { expected: num_pixels * bpp, actual: data.len() });
+        }
+
+        let texels = data.chunks_exact(bpp).take(num_pixels);
+        let u16_at = |t: &[u8], i: usize| u16::from_le_bytes([t[2 * i], t[2 * i + 1]]);
+        let u32_at = |t: &[u8], i: usize| u32::from_le_bytes([t[4 * i], t[4 * i + 1], t[4 * i + 2], t[4 * i + 3]]);
+        let f16_at = |t: &[u8], i: usize| Half::from_bits(u16_at(t, i)).to_f32();
+        let unorm16_at = |t: &[u8], i: usize| u16_at(t, i) as f32 / 65535.0;
+
+        let pixels = match format {
+            R8Unorm => HostPixels::Rgba8(texels.flat_map(|t| [t[0], 0, 0, 255]).collect()),
+            RG8Unorm => HostPixels::Rgba8(texels.flat_map(|t| [t[0], t[1], 0, 255]).collect()),
+            RGBA8Unorm => HostPixels::Rgba8(texels.flatten().copied().collect()),
+            RGBX8Unorm => HostPixels::Rgba8(texels.flat_map(|t| [t[0], t[1], t[2], 255]).collect()),
+            BGRA8Unorm => HostPixels::Rgba8(texels.flat_map(|t| [t[2], t[1], t[0], t[3]]).collect()),
+            BGRX8Unorm => HostPixels::Rgba8(texels.flat_map(|t| [t[2], t[1], t[0], 255]).collect()),
+            R16Float => HostPixels::Rgba32Float(texels.flat_map(|t| [f16_at(t, 0), 0.0, 0.0, 1.0]).collect()),
+            RG16Float => HostPixels::Rgba32Float(texels.flat_map(|t| [f16_at(t, 0), f16_at(t, 1), 0.0, 1.0]).collect()),
+            RGBA16Float => HostPixels::Rgba32Float(texels.flat_map(|t| [0, 1, 2, 3].map(|i| f16_at(t, i))).collect()),
+            RGBX16Float => {
+                HostPixels::Rgba32Float(texels.flat_map(|t| [f16_at(t, 0), f16_at(t, 1), f16_at(t, 2), 1.0]).collect())
+            }
+            R16Unorm => HostPixels::Rgba32Float(texels.flat_map(|t| [unorm16_at(t, 0), 0.0, 0.0, 1.0]).collect()),
+            RGBA16Unorm => {
+                HostPixels::Rgba32Float(texels.flat_map(|t| [0, 1, 2, 3].map(|i| unorm16_at(t, i))).collect())
+            }
+            RGBX16Unorm => HostPixels::Rgba32Float(
+                texels.flat_map(|t| [unorm16_at(t, 0), unorm16_at(t, 1), unorm16_at(t, 2), 1.0]).collect(),
+            ),
+            R32Float => {
+                HostPixels::Rgba32Float(texels.flat_map(|t| [f32::from_bits(u32_at(t, 0)), 0.0, 0.0, 1.0]).collect())
+            }
+            RG32Float => HostPixels::Rgba32Float(
+                texels.flat_map(|t| [f32::from_bits(u32_at(t, 0)), f32::from_bits(u32_at(t, 1)), 0.0, 1.0]).collect(),
+            ),
+            RGBA32Float => HostPixels::Rgba32Float(
+                texels.flat_map(|t| [0, 1, 2, 3].map(|i| f32::from_bits(u32_at(t, i)))).collect(),
+            ),
+            RGBX32Float => HostPixels::Rgba32Float(
+                texels
+                    .flat_map(|t| {
+                        let [r, g, b] = [0, 1, 2].map(|i| f32::from_bits(u32_at(t, i)));
+                        [r, g, b, 1.0]
+                    })
+                    .collect(),
+            ),
+            RGB10A2Unorm => HostPixels::Rgba32Float(
+                texels
+                    .flat_map(|t| {
+                        let v = u32_at(t, 0);
+                        let [r, g, b] = [0, 10, 20].map(|shift| ((v >> shift) & 0x3ff) as f32 / 1023.0);
+                        [r, g, b, (v >> 30) as f32 / 3.0]
+                    })
+                    .collect(),
+            ),
+            RG11B10Float => HostPixels::Rgba32Float(
+                texels
+                    .flat_map(|t| {
+                        let v = u32_at(t, 0);
+                        [
+                            unpack_small_float(v & 0x7ff, 6),
+                            unpack_small_float((v >> 11) & 0x7ff, 6),
+                            unpack_small_float(v >> 22, 5),
+                            1.0,
+                        ]
+                    })
+                    .collect(),
+            ),
+        };
+
+        Ok(Self { width, height, pixels })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &HostPixels {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> HostPixels {
+        self.pixels
+    }
+
+    /// Returns the pixels as RGBA8, clamping float components to [0, 1].
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        match &self.pixels {
+            HostPixels::Rgba8(pixels) => pixels.clone(),
+            HostPixels::Rgba32Float(pixels) => {
+                pixels.iter().map(|&c| (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8).collect()
+            }
+        }
+    }
+
+    /// Encodes the image as an 8-bit RGBA PNG.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: std::io::Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba8())
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), png::EncodingError> {
+        let file = std::fs::File::create(path)?;
+        self.write_png(std::io::BufWriter::new(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packs a float into an unsigned 11- or 10-bit float; only exact values are used below.
+    fn pack_small_float(value: f32, mant_bits: u32) -> u32 {
+        if value == 0.0 {
+            return 0;
+        }
+
+        let bits = value.to_bits();
+        let exp = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+        let mant = (bits & 0x7f_ffff) >> (23 - mant_bits);
+        ((exp as u32) << mant_bits) | mant
+    }
+
+    fn pack_rg11b10(r: f32, g: f32, b: f32) -> [u8; 4] {
+        (pack_small_float(r, 6) | (pack_small_float(g, 6) << 11) | (pack_small_float(b, 5) << 22)).to_le_bytes()
+    }
+
+    fn pack_rgb10a2(r: u32, g: u32, b: u32, a: u32) -> [u8; 4] {
+        (r | (g << 10) | (b << 20) | (a << 30)).to_le_bytes()
+    }
+
+    fn float_pixels(image: &HostImage) -> &[f32] {
+        match image.get_pixels() {
+            HostPixels::Rgba32Float(pixels) => pixels,
+            pixels => panic!("expected float pixels, got {:?}", pixels),
+        }
+    }
+
+    #[test]
+    fn rg11b10_float() {
+        let colors = [[0.0, 0.0, 0.0], [1.0, 0.5, 2.0], [0.25, 65024.0, 0.125], [1.5, 3.0, 0.75]];
+        let data: Vec<u8> = colors.iter().flat_map(|&[r, g, b]| pack_rg11b10(r, g, b)).collect();
+
+        let image = HostImage::decode(HostFormat::RG11B10Float, 2, 2, &data).unwrap();
+        let expected: Vec<f32> = colors.iter().flat_map(|&[r, g, b]| [r, g, b, 1.0]).collect();
+        assert_eq!(float_pixels(&image), &expected[..]);
+    }
+
+    #[test]
+    fn rg11b10_special_values() {
+        let data = (0x7c0u32 | (0x7c1 << 11) | (0x001 << 22)).to_le_bytes();
+
+        let image = HostImage::decode(HostFormat::RG11B10Float, 1, 1, &data).unwrap();
+        let pixels = float_pixels(&image);
+        assert_eq!(pixels[0], f32::INFINITY);
+        assert!(pixels[1].is_nan());
+        assert_eq!(pixels[2], 2f32.powi(-14) / 32.0);
+    }
+
+    #[test]
+    fn rgb10a2_unorm() {
+        let data: Vec<u8> = [pack_rgb10a2(0, 1023, 341, 3), pack_rgb10a2(1023, 0, 682, 1)].concat();
+
+        let image = HostImage::decode(HostFormat::RGB10A2Unorm, 2, 1, &data).unwrap();
+        assert_eq!(float_pixels(&image), &[0.0, 1.0, 341.0 / 1023.0, 1.0, 1.0, 0.0, 682.0 / 1023.0, 1.0 / 3.0][..]);
+        assert_eq!(image.to_rgba8(), [0, 255, 85, 255, 255, 0, 170, 85]);
+    }
+
+    #[test]
+    fn bgra8_unorm() {
+        let rgba = [[10u8, 20, 30, 40], [255, 128, 0, 255]];
+        let data: Vec<u8> = rgba.iter().flat_map(|&[r, g, b, a]| [b, g, r, a]).collect();
+
+        let image = HostImage::decode(HostFormat::BGRA8Unorm, 1, 2, &data).unwrap();
+        assert_eq!(image.get_pixels(), &HostPixels::Rgba8(rgba.concat()));
+        assert_eq!((image.get_width(), image.get_height()), (1, 2));
+    }
+
+    #[test]
+    fn missing_components() {
+        let image = HostImage::decode(HostFormat::BGRX8Unorm, 1, 1, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(image.get_pixels(), &HostPixels::Rgba8(vec![3, 2, 1, 255]));
+
+        let image = HostImage::decode(HostFormat::RG8Unorm, 1, 1, &[7, 9]).unwrap();
+        assert_eq!(image.get_pixels(), &HostPixels::Rgba8(vec![7, 9, 0, 255]));
+
+        let data = [Half::from_f32(0.5), Half::from_f32(-2.0)].map(|h| h.to_bits().to_le_bytes()).concat();
+        let image = HostImage::decode(HostFormat::RG16Float, 1, 1, &data).unwrap();
+        assert_eq!(float_pixels(&image), &[0.5, -2.0, 0.0, 1.0][..]);
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(
+            HostImage::decode(HostFormat::RGBA16Float, 2, 2, &[0; 31]),
+            Err(DecodeError::Truncated { expected: 32, actual: 31 })
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn write_png() {
+        let image = HostImage::decode(HostFormat::RGBA8Unorm, 1, 1, &[1, 2, 3, 4]).unwrap();
+
+        let mut encoded = Vec::new();
+        image.write_png(&mut encoded).unwrap();
+
+        let mut reader = png::Decoder::new(&encoded[..]).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn half_round_trip() {
+        for value in [0.0, -0.0, 1.0, -1.5, 0.333, 65504.0, 6.1e-5, 5.96e-8, f32::INFINITY] {
+            let half = Half::from_f32(value);
+            assert_eq!(Half::from_f32(half.to_f32()), half, "{}", value);
+        }
+
+        assert_eq!(Half::from_f32(1.0).to_bits(), 0x3c00);
+        assert_eq!(Half::from_f32(65536.0).to_bits(), 0x7c00);
+        assert!(Half::from_f32(f32::NAN).to_f32().is_nan());
+    }
+}
@@ -0,0 +1,140 @@
+use std::io::{self, Read, Write};
+
+use deko3d_disasm::CAPTURE_MAGIC;
+use deko3d_sys::*;
+
+use crate::{CmdBuf, DekoError, Result};
+
+pub use deko3d_disasm::CAPTURE_FORMAT_VERSION;
+
+/// Capture storage that must remain unused for a capture to be considered complete.
+///
+/// deko3d can't grow capture storage, so a capture that ends closer than this to the end of its storage
+/// is treated as possibly truncated. No single command recorded during a capture may be larger than this.
+pub const CAPTURE_HEADROOM_WORDS: u32 = 0x100;
+
+const CAPTURE_INITIAL_WORDS: u32 = 0x400;
+// Saved streams longer than this are rejected before anything is allocated for them.
+const CAPTURE_MAX_WORDS: usize = 0x1000_0000;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapturedCmds {
+    words: Vec<u32>,
+}
+
+impl CapturedCmds {
+    pub fn from_words(words: Vec<u32>) -> Self {
+        CapturedCmds { words }
+    }
+
+    pub fn into_words(self) -> Vec<u32> {
+        self.words
+    }
+
+    pub fn as_words(&self) -> &[u32] {
+        &self.words
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn disassemble(&self) -> Vec<deko3d_disasm::Command> {
+        deko3d_disasm::disassemble(&self.words)
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&CAPTURE_MAGIC)?;
+        writer.write_all(&CAPTURE_FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.words.len() as u32).to_le_bytes())?;
+
+        for word in self.words.iter() {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+
+        if header[0..4] != CAPTURE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a captured command stream"));
+        }
+
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != CAPTURE_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported captured command stream version {}", version),
+            ));
+        }
+
+        let len = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        if len > CAPTURE_MAX_WORDS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("captured command stream of {} words is too long", len),
+            ));
+        }
+
+        // Read incrementally instead of trusting the header with the allocation size.
+        let mut bytes = Vec::new();
+        reader.take(len as u64 * 4).read_to_end(&mut bytes)?;
+        if bytes.len() != len * 4 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "captured command stream is truncated"));
+        }
+
+        let words = bytes.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect();
+
+        Ok(CapturedCmds { words })
+    }
+}
+
+impl CmdBuf<'_> {
+    /// Captures the commands recorded by `f`, growing the storage until they fit.
+    ///
+    /// Every time the storage runs out `f` is called again with twice as much, so it must record the
+    /// same commands on every call and shouldn't have other side effects. Use
+    /// [`CmdBuf::capture_with_capacity`] to run `f` exactly once.
+    pub fn capture<F: FnMut(&Self)>(&self, mut f: F) -> CapturedCmds {
+        let mut max_words = CAPTURE_INITIAL_WORDS;
+
+        loop {
+            match self.capture_with_capacity(max_words, &mut f) {
+                Ok(cmds) => return cmds,
+                Err(_) => max_words = max_words.checked_mul(2).expect("captured commands are too large"),
+            }
+        }
+    }
+
+    /// Captures the commands recorded by `f` into storage of `max_words` words.
+    ///
+    /// Returns `DekoError::OutOfMemory` if less than [`CAPTURE_HEADROOM_WORDS`] of the storage were
+    /// left, in which case the capture may be truncated and is discarded.
+    pub fn capture_with_capacity<F: FnOnce(&Self)>(&self, max_words: u32, f: F) -> Result<CapturedCmds> {
+        let mut storage = vec![0u32; max_words as usize];
+
+        let len = unsafe {
+            dkCmdBufBeginCaptureCmds(self.0, storage.as_mut_ptr(), max_words);
+            f(self);
+            dkCmdBufEndCaptureCmds(self.0)
+        };
+
+        if max_words.saturating_sub(len) < CAPTURE_HEADROOM_WORDS {
+            return Err(DekoError::OutOfMemory);
+        }
+
+        storage.truncate(len as usize);
+        Ok(CapturedCmds { words: storage })
+    }
+
+    pub fn replay(&self, cmds: &CapturedCmds) {
+        self.replay_cmds(&cmds.words)
+    }
+}
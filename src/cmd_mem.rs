@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use deko3d_sys::*;
+
+use crate::pool::SharedPool;
+use crate::{align_up, CmdBuf, Device, Fence, MemBlock, MemBlockMaker, Queue};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CmdMemStats {
+    pub current_frame: u64,
+    pub last_frame: u64,
+    pub peak_frame: u64,
+    pub allocated: u64,
+}
+
+struct CmdMemPoolState {
+    chunk_size: u32,
+    free: Vec<MemBlock>,
+    active: Vec<MemBlock>,
+    pending: VecDeque<(Fence, Vec<MemBlock>)>,
+    stats: CmdMemStats,
+    // Number of live command buffers drawing memory from the pool.
+    cmd_bufs: usize,
+}
+
+impl CmdMemPoolState {
+    fn recycle(&mut self) {
+        while let Some((fence, _)) = self.pending.front_mut() {
+            if fence.wait(0).is_err() {
+                break;
+            }
+
+            let (_, chunks) = self.pending.pop_front().unwrap();
+            self.free.extend(chunks);
+        }
+    }
+
+    fn alloc_chunk(&mut self, device: &Device, min_size: u32) -> (DkMemBlock, u32) {
+        self.recycle();
+
+        let chunk = match self.free.iter().position(|mem| mem.get_size() >= min_size) {
+            Some(index) => self.free.swap_remove(index),
+            None => {
+                let size = self.chunk_size.max(align_up(min_size, DK_MEMBLOCK_ALIGNMENT));
+                let chunk = MemBlockMaker::new(device, size).create();
+
+                self.stats.allocated += size as u64;
+                chunk
+            }
+        };
+
+        let raw = (chunk.0, chunk.get_size());
+        self.stats.current_frame += raw.1 as u64;
+        self.active.push(chunk);
+
+        raw
+    }
+}
+
+impl Drop for CmdMemPoolState {
+    fn drop(&mut self) {
+        for (fence, _) in self.pending.iter_mut() {
+            let _ = fence.wait_no_timeout();
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CmdMemPool<'d>(Arc<SharedPool<'d, CmdMemPoolState>>);
+
+impl<'d> CmdMemPool<'d> {
+    pub fn new(device: &'d Device, chunk_size: u32) -> Self {
+        CmdMemPool(Arc::new(SharedPool::new(
+            device,
+            CmdMemPoolState {
+                chunk_size: align_up(chunk_size, DK_MEMBLOCK_ALIGNMENT),
+                free: Vec::new(),
+                active: Vec::new(),
+                pending: VecDeque::new(),
+                stats: CmdMemStats::default(),
+                cmd_bufs: 0,
+            },
+        )))
+    }
+
+    /// Marks the end of the frame on `queue` and clears `cmd_bufs`, so the chunks they were recording into can be
+    /// recycled once the frame completes.
+    ///
+    /// `cmd_bufs` must hold every live command buffer created from this pool; a buffer left out would keep
+    /// recording into a chunk that is handed to another buffer afterwards.
+    pub fn end_frame<'b, 'c: 'b>(&self, queue: &Queue, cmd_bufs: impl IntoIterator<Item = &'b mut CmdBuf<'c>>) {
+        let mut state = self.lock();
+
+        let mut cleared = 0;
+        for cmd_buf in cmd_bufs {
+            debug_assert!(cmd_buf.uses_memory_pool(self), "command buffer does not draw from this pool");
+            cmd_buf.clear();
+            cleared += 1;
+        }
+        debug_assert_eq!(cleared, state.cmd_bufs, "end_frame must clear every command buffer using the pool");
+
+        let mut fence = Fence::new();
+        queue.signal_fence(&mut fence, false);
+
+        let chunks = std::mem::take(&mut state.active);
+        state.pending.push_back((fence, chunks));
+
+        let usage = std::mem::take(&mut state.stats.current_frame);
+        state.stats.last_frame = usage;
+        state.stats.peak_frame = state.stats.peak_frame.max(usage);
+    }
+
+    pub fn recycle(&self) {
+        self.lock().recycle();
+    }
+
+    pub fn get_stats(&self) -> CmdMemStats {
+        self.lock().stats
+    }
+
+    pub(crate) fn attach(&self) -> CmdMemPoolUser<'d> {
+        self.lock().cmd_bufs += 1;
+        CmdMemPoolUser(self.clone())
+    }
+
+    pub(crate) fn user_data(&self) -> *mut std::ffi::c_void {
+        Arc::as_ptr(&self.0) as *mut _
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CmdMemPoolState> {
+        self.0.lock()
+    }
+}
+
+/// A command buffer's reference to its pool, counted so that [`CmdMemPool::end_frame`] can check it sees every buffer.
+pub(crate) struct CmdMemPoolUser<'d>(CmdMemPool<'d>);
+
+impl<'d> CmdMemPoolUser<'d> {
+    pub(crate) fn get_pool(&self) -> &CmdMemPool<'d> {
+        &self.0
+    }
+}
+
+impl Drop for CmdMemPoolUser<'_> {
+    fn drop(&mut self) {
+        self.0.lock().cmd_bufs -= 1;
+    }
+}
+
+pub(crate) unsafe extern "C" fn cmd_mem_pool_add_mem(
+    user_data: *mut std::ffi::c_void,
+    cmd_buf: DkCmdBuf,
+    min_req_size: usize,
+) {
+    let pool = &*(user_data as *const SharedPool<CmdMemPoolState>);
+    let mut state = pool.lock();
+
+    let (mem, size) = state.alloc_chunk(pool.get_device(), min_req_size as u32);
+    dkCmdBufAddMemory(cmd_buf, mem, 0, size);
+}
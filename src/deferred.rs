@@ -0,0 +1,159 @@
+use std::any::type_name;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use deko3d_sys::DkFence;
+
+use crate::{DekoError, Device, Fence, Queue};
+
+struct Parked {
+    fence: Fence,
+    type_name: &'static str,
+    // Only held so that it is dropped once the fence has signaled.
+    _resource: Box<dyn std::any::Any + Send>,
+}
+
+#[derive(Default)]
+struct DeferredDeleterState {
+    parked: Vec<Parked>,
+}
+
+/// Keeps resources alive until the GPU work that uses them has completed.
+///
+/// Queues created from a [`QueueMaker`](crate::QueueMaker) record the last submission using a
+/// resource: submitting a list records its [`CmdBuf`](crate::CmdBuf) and the memory added to it,
+/// and presenting records the [`Swapchain`](crate::Swapchain). Dropping such a resource before that
+/// submission completes parks it here instead of destroying it. Memory the GPU reaches through its
+/// address, such as textures and buffers, is only recorded through [`Queue::mark_used`].
+///
+/// Other resources can be handed to the deleter directly, together with the fence of the last
+/// submission using them.
+///
+/// Each [`Device`] owns one deleter and flushes it when destroyed, so parked resources never outlive
+/// the device.
+pub struct DeferredDeleter(Mutex<DeferredDeleterState>);
+
+impl DeferredDeleter {
+    pub(crate) fn new() -> Self {
+        DeferredDeleter(Mutex::default())
+    }
+
+    /// Destroys `resource` once `fence` has signaled.
+    pub fn defer<T: Send + 'static>(&self, resource: T, fence: Fence) {
+        self.park(Box::new(resource), fence, type_name::<T>());
+    }
+
+    /// Destroys `resource` once everything submitted to `queue` so far has completed.
+    pub fn defer_after<T: Send + 'static>(&self, resource: T, queue: &Queue) {
+        let mut fence = Fence::new();
+        queue.signal_fence(&mut fence, true);
+
+        self.defer(resource, fence);
+    }
+
+    /// Destroys every parked resource whose fence has signaled, returning how many were destroyed.
+    pub fn collect(&self) -> usize {
+        let mut ready = Vec::new();
+
+        {
+            let mut state = self.lock();
+            let mut i = 0;
+            while i < state.parked.len() {
+                if state.parked[i].fence.wait(0) != Err(DekoError::Timeout) {
+                    ready.push(state.parked.swap_remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        // Resources are dropped outside the lock since their destructors may park more of them.
+        ready.len()
+    }
+
+    pub fn get_pending_count(&self) -> usize {
+        self.lock().parked.len()
+    }
+
+    /// Waits for every parked resource's fence and destroys them all.
+    pub fn flush(&self) {
+        let parked = std::mem::take(&mut self.lock().parked);
+
+        for mut parked in parked {
+            let _ = parked.fence.wait_no_timeout();
+        }
+    }
+
+    pub(crate) fn report_leaks(&self) {
+        let state = self.lock();
+        if state.parked.is_empty() {
+            return;
+        }
+
+        eprintln!("deko3d: {} resource(s) still in flight at device destruction:", state.parked.len());
+        for parked in state.parked.iter() {
+            eprintln!("  {}", parked.type_name);
+        }
+    }
+
+    fn park(&self, resource: Box<dyn std::any::Any + Send>, fence: Fence, type_name: &'static str) {
+        self.lock().parked.push(Parked { fence, type_name, _resource: resource });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DeferredDeleterState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A raw handle together with the function destroying it, run when dropped.
+struct Destroy<H: Copy>(H, unsafe extern "C" fn(H));
+
+// The handle is owned by the deleter alone and the GPU is done with it once it is destroyed.
+unsafe impl<H: Copy> Send for Destroy<H> {}
+
+impl<H: Copy> Drop for Destroy<H> {
+    fn drop(&mut self) {
+        unsafe { (self.1)(self.0) }
+    }
+}
+
+/// The fence of the last submission using a resource, and the deleter to park the resource on if it
+/// is dropped before that submission completes.
+#[derive(Default)]
+pub(crate) struct LastUse(Mutex<Option<(Fence, Arc<DeferredDeleter>)>>);
+
+impl LastUse {
+    pub(crate) fn record(&self, fence: DkFence, deleter: &Arc<DeferredDeleter>) {
+        let fence = unsafe { Fence::from_raw(fence) };
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some((fence, deleter.clone()));
+    }
+
+    /// Destroys `handle`, the handle of a `T`, right away if its last use has completed and parks it
+    /// on the deleter otherwise.
+    pub(crate) fn destroy<T: ?Sized, H: Copy + 'static>(&mut self, handle: H, destroy: unsafe extern "C" fn(H)) {
+        let resource = Destroy(handle, destroy);
+
+        if let Some((mut fence, deleter)) = self.0.get_mut().unwrap_or_else(PoisonError::into_inner).take() {
+            if fence.wait(0) == Err(DekoError::Timeout) {
+                deleter.park(Box::new(resource), fence, type_name::<T>());
+            }
+        }
+    }
+}
+
+impl Device {
+    /// Deleter whose resources are destroyed no later than this device.
+    pub fn get_deleter(&self) -> &DeferredDeleter {
+        self.get_shared_deleter()
+    }
+
+    pub(crate) fn get_shared_deleter(&self) -> &Arc<DeferredDeleter> {
+        self.1.deleter.get_or_init(|| Arc::new(DeferredDeleter::new()))
+    }
+
+    pub fn collect_garbage(&self) -> usize {
+        match self.1.deleter.get() {
+            Some(deleter) => deleter.collect(),
+            None => 0,
+        }
+    }
+}
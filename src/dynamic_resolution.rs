@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use deko3d_sys::*;
+
+use crate::profiler::{read_report_timestamp, REPORT_SIZE};
+use crate::{
+    align_up, timestamp_to_ns, CmdBuf, Counter, Device, MemBlock, MemBlockMaker, RenderPassDesc, Scissor, Swapchain,
+    Viewport,
+};
+
+/// Picks a render resolution from measured GPU frame time and presents it through the swapchain crop.
+///
+/// Each frame is bracketed by two GPU timestamps. Once a frame slot comes around again its time is read
+/// back, and the scale is adjusted so the frame fits the target time, assuming cost grows with the pixel
+/// count. The scale only grows back once frames are faster than the target by the hysteresis margin, so
+/// it doesn't oscillate around the budget.
+///
+/// A render pass sets viewport and scissor 0 to its render area when it begins, overriding what
+/// [`DynamicResolution::begin_frame`] set. Passes rendering at the scaled size need it as their render
+/// area, which [`DynamicResolution::apply_render_area`] sets.
+pub struct DynamicResolution {
+    queries: MemBlock,
+    pending: Vec<bool>,
+    current: usize,
+    full_width: u32,
+    full_height: u32,
+    target_ns: u64,
+    min_scale: f32,
+    max_scale: f32,
+    hysteresis: f32,
+    scale: f32,
+    gpu_time_ns: Option<u64>,
+}
+
+impl DynamicResolution {
+    /// Frame times are measured through `frames_in_flight` timestamp slots used in turn. Passing fewer
+    /// slots than frames the GPU may be working on makes the controller scale from timestamps that
+    /// haven't been written yet.
+    pub fn new(
+        device: &Device,
+        full_width: u32,
+        full_height: u32,
+        frames_in_flight: u32,
+        target_frame_time: Duration,
+    ) -> Self {
+        assert!(frames_in_flight > 0);
+
+        let queries =
+            MemBlockMaker::new(device, align_up(frames_in_flight * 2 * REPORT_SIZE, DK_MEMBLOCK_ALIGNMENT)).create();
+
+        DynamicResolution {
+            queries,
+            pending: vec![false; frames_in_flight as usize],
+            current: 0,
+            full_width,
+            full_height,
+            target_ns: target_frame_time.as_nanos() as u64,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            hysteresis: 0.1,
+            scale: 1.0,
+            gpu_time_ns: None,
+        }
+    }
+
+    pub fn set_scale_limits(&mut self, min_scale: f32, max_scale: f32) -> &mut Self {
+        assert!(0.0 < min_scale && min_scale <= max_scale && max_scale <= 1.0);
+
+        self.min_scale = min_scale;
+        self.max_scale = max_scale;
+        self.scale = self.scale.clamp(min_scale, max_scale);
+        self
+    }
+
+    pub fn get_scale_limits(&self) -> (f32, f32) {
+        (self.min_scale, self.max_scale)
+    }
+
+    /// Sets how much faster than the target a frame must be, as a fraction of the target, before the
+    /// scale is raised.
+    pub fn set_hysteresis(&mut self, hysteresis: f32) -> &mut Self {
+        self.hysteresis = hysteresis.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn get_hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    pub fn set_target_frame_time(&mut self, target_frame_time: Duration) -> &mut Self {
+        self.target_ns = target_frame_time.as_nanos() as u64;
+        self
+    }
+
+    pub fn get_target_frame_time(&self) -> Duration {
+        Duration::from_nanos(self.target_ns)
+    }
+
+    /// Sets the size of the framebuffers being rendered into, e.g. after a [`crate::Presenter::resize`].
+    pub fn set_full_size(&mut self, width: u32, height: u32) -> &mut Self {
+        self.full_width = width;
+        self.full_height = height;
+        self
+    }
+
+    pub fn get_full_size(&self) -> (u32, u32) {
+        (self.full_width, self.full_height)
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn get_render_size(&self) -> (u32, u32) {
+        let scaled = |size: u32| ((size as f32 * self.scale).round() as u32).clamp(1, size.max(1));
+        (scaled(self.full_width), scaled(self.full_height))
+    }
+
+    /// Returns the GPU time of the most recently measured frame.
+    pub fn get_gpu_time(&self) -> Option<Duration> {
+        self.gpu_time_ns.map(Duration::from_nanos)
+    }
+
+    /// Updates the scale and starts timing a frame on `cmd_buf`, setting viewport and scissor 0 to the
+    /// scaled render area for rendering outside of render passes.
+    ///
+    /// The frame time fed into the scale is read from the slot this frame reuses, so the frame that last
+    /// wrote it has to be complete; a [`crate::FrameManager`] with as many frames in flight has already
+    /// waited for it once its `begin_frame` returns.
+    pub fn begin_frame(&mut self, cmd_buf: &CmdBuf) {
+        if self.pending[self.current] {
+            let ns = self.read_frame_time(self.current);
+            self.gpu_time_ns = Some(ns);
+            self.update_scale(ns);
+        }
+
+        cmd_buf.report_counter(Counter::Timestamp, self.report_addr(self.current, 0));
+
+        let (width, height) = self.get_render_size();
+        cmd_buf.set_viewports(
+            0,
+            &[Viewport { x: 0.0, y: 0.0, width: width as f32, height: height as f32, near: 0.0, far: 1.0 }],
+        );
+        cmd_buf.set_scissors(0, &[Scissor { x: 0, y: 0, width, height }]);
+    }
+
+    /// Makes `desc` render to the scaled render area. Call after [`DynamicResolution::begin_frame`], since
+    /// that is where the scale changes.
+    pub fn apply_render_area(&self, desc: &mut RenderPassDesc) {
+        desc.set_render_area(Some(self.get_render_size()));
+    }
+
+    /// Stops timing the current frame on `cmd_buf`.
+    pub fn end_frame(&mut self, cmd_buf: &CmdBuf) {
+        cmd_buf.report_counter(Counter::Timestamp, self.report_addr(self.current, 1));
+
+        self.pending[self.current] = true;
+        self.current = (self.current + 1) % self.pending.len();
+    }
+
+    /// Crops presentation to the area rendered this frame. Call before presenting.
+    pub fn apply_crop(&self, swapchain: &Swapchain) {
+        let (width, height) = self.get_render_size();
+        swapchain.set_crop(0, 0, width as i32, height as i32);
+    }
+
+    fn report_offset(&self, slot: usize, index: u32) -> u32 {
+        (slot as u32 * 2 + index) * REPORT_SIZE
+    }
+
+    fn report_addr(&self, slot: usize, index: u32) -> DkGpuAddr {
+        self.queries.get_gpu_addr() + self.report_offset(slot, index) as DkGpuAddr
+    }
+
+    fn read_frame_time(&self, slot: usize) -> u64 {
+        let begin = read_report_timestamp(&self.queries, self.report_offset(slot, 0));
+        let end = read_report_timestamp(&self.queries, self.report_offset(slot, 1));
+        timestamp_to_ns(end.wrapping_sub(begin))
+    }
+
+    fn update_scale(&mut self, gpu_time_ns: u64) {
+        if gpu_time_ns == 0 {
+            return;
+        }
+
+        let too_slow = gpu_time_ns > self.target_ns;
+        let fast_enough = (gpu_time_ns as f32) < self.target_ns as f32 * (1.0 - self.hysteresis);
+        if too_slow || fast_enough {
+            let ratio = self.target_ns as f32 / gpu_time_ns as f32;
+            self.scale = (self.scale * ratio.sqrt()).clamp(self.min_scale, self.max_scale);
+        }
+    }
+}
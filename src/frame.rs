@@ -0,0 +1,138 @@
+use deko3d_sys::*;
+
+use crate::{align_up, CmdBuf, CmdBufMaker, Device, Fence, MemBlock, MemBlockFlags, MemBlockMaker, Queue, Swapchain};
+
+struct FrameSlot<'a> {
+    cmd_buf: CmdBuf<'a>,
+    fence: Fence,
+    // Set once the frame is submitted, so that a frame dropped without `end_frame` leaves nothing to wait for.
+    frame_index: Option<u64>,
+    deferred: Vec<Box<dyn FnOnce()>>,
+}
+
+pub struct Frame<'a> {
+    queue: &'a Queue,
+    swapchain: &'a Swapchain,
+    slot: usize,
+    image_slot: i32,
+    index: u64,
+}
+
+impl<'a> Frame<'a> {
+    pub fn get_image_slot(&self) -> i32 {
+        self.image_slot
+    }
+
+    pub fn get_index(&self) -> u64 {
+        self.index
+    }
+}
+
+pub struct FrameManager<'a> {
+    // The command buffers are dropped before `cmd_mem`, which backs them.
+    slots: Vec<FrameSlot<'a>>,
+    cleanup_hooks: Vec<Box<dyn FnMut(u64)>>,
+    cmd_mem: MemBlock,
+    slice_size: u32,
+    current: usize,
+    next_index: u64,
+}
+
+impl<'a> FrameManager<'a> {
+    pub fn new(device: &'a Device, frames_in_flight: u32, cmd_mem_size: u32) -> Self {
+        assert!(frames_in_flight > 0);
+
+        let slice_size = align_up(cmd_mem_size, DK_CMDMEM_ALIGNMENT);
+        let cmd_mem = MemBlockMaker::new(device, align_up(slice_size * frames_in_flight, DK_MEMBLOCK_ALIGNMENT))
+            .set_flags(MemBlockFlags::CpuUncached | MemBlockFlags::GpuCached)
+            .create();
+
+        let slots = (0..frames_in_flight)
+            .map(|_| FrameSlot {
+                cmd_buf: CmdBufMaker::new(device).create(),
+                fence: Fence::new(),
+                frame_index: None,
+                deferred: Vec::new(),
+            })
+            .collect();
+
+        FrameManager { slots, cleanup_hooks: Vec::new(), cmd_mem, slice_size, current: 0, next_index: 0 }
+    }
+
+    pub fn get_frames_in_flight(&self) -> u32 {
+        self.slots.len() as u32
+    }
+
+    /// Registers a hook that runs with the index of every frame once the GPU is done with it.
+    pub fn add_cleanup_hook<F: FnMut(u64) + 'static>(&mut self, hook: F) {
+        self.cleanup_hooks.push(Box::new(hook));
+    }
+
+    /// Runs `f` once the GPU is done with the frame currently being recorded.
+    pub fn defer<F: FnOnce() + 'static>(&mut self, f: F) {
+        self.slots[self.current].deferred.push(Box::new(f));
+    }
+
+    pub fn get_cmd_buf(&self) -> &CmdBuf<'a> {
+        &self.slots[self.current].cmd_buf
+    }
+
+    pub fn begin_frame<'f>(&mut self, queue: &'f Queue, swapchain: &'f Swapchain) -> Frame<'f> {
+        let slot = self.current;
+        self.retire(slot);
+
+        let offset = slot as u32 * self.slice_size;
+        let cmd_buf = &mut self.slots[slot].cmd_buf;
+        cmd_buf.clear();
+        unsafe { cmd_buf.add_memory_unchecked(&self.cmd_mem, offset, self.slice_size) };
+
+        let image_slot = queue.acquire_image(swapchain);
+        let index = self.next_index;
+        self.next_index += 1;
+
+        Frame { queue, swapchain, slot, image_slot, index }
+    }
+
+    pub fn end_frame(&mut self, frame: Frame) {
+        debug_assert_eq!(frame.slot, self.current, "frames must be ended in the order they were begun");
+
+        let slot = &mut self.slots[frame.slot];
+        frame.queue.submit_commands(slot.cmd_buf.finish_list());
+        frame.queue.signal_fence(&mut slot.fence, false);
+        frame.queue.present_image(frame.swapchain, frame.image_slot);
+        slot.frame_index = Some(frame.index);
+
+        self.current = (self.current + 1) % self.slots.len();
+    }
+
+    /// Waits for every frame in flight and runs their cleanup.
+    pub fn wait_idle(&mut self) {
+        for slot in 0..self.slots.len() {
+            self.retire(slot);
+        }
+    }
+
+    fn retire(&mut self, slot: usize) {
+        let slot = &mut self.slots[slot];
+        let index = match slot.frame_index.take() {
+            Some(index) => index,
+            None => return,
+        };
+
+        let _ = slot.fence.wait_no_timeout();
+
+        for f in slot.deferred.drain(..) {
+            f();
+        }
+
+        for hook in self.cleanup_hooks.iter_mut() {
+            hook(index);
+        }
+    }
+}
+
+impl Drop for FrameManager<'_> {
+    fn drop(&mut self) {
+        self.wait_idle();
+    }
+}
@@ -1,7 +1,10 @@
 use std::borrow::{Borrow, BorrowMut};
+use std::cell::Cell;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 use bitflags::bitflags;
 use deko3d_sys::*;
@@ -27,9 +30,53 @@ pub use deko3d_sys::DK_SHADER_CODE_UNUSABLE_SIZE;
 pub use deko3d_sys::DK_UNIFORM_BUF_ALIGNMENT;
 pub use deko3d_sys::DK_UNIFORM_BUF_MAX_SIZE;
 
-pub type Result = std::result::Result<(), DekoError>;
+mod capture;
+mod cmd_mem;
+mod deferred;
+mod dynamic_resolution;
+mod frame;
+mod mipmap;
+mod parallel;
+mod pool;
+mod presenter;
+mod profiler;
+mod readback;
+mod recovery;
+mod render_graph;
+mod render_pass;
+mod scheduler;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod sync;
+mod thread_safety;
+mod timeline;
+mod uniform;
+mod vertex;
+
+pub use capture::*;
+pub use cmd_mem::*;
+pub use deferred::*;
+pub use dynamic_resolution::*;
+pub use deko3d_derive::{Std140, Std430, Vertex};
+pub use deko3d_disasm as disasm;
+pub use frame::*;
+pub use parallel::*;
+pub use presenter::*;
+pub use profiler::*;
+pub use readback::*;
+pub use recovery::*;
+pub use render_graph::*;
+pub use render_pass::*;
+pub use scheduler::*;
+pub use sync::*;
+pub use timeline::*;
+pub use uniform::*;
+pub use vertex::*;
+
+pub type Result<T = ()> = std::result::Result<T, DekoError>;
 
-enum DekoError {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DekoError {
     Fail,
     Timeout,
     OutOfMemory,
@@ -42,6 +89,25 @@ enum DekoError {
     Unknown,
 }
 
+impl std::fmt::Display for DekoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DekoError::Fail => "operation failed",
+            DekoError::Timeout => "operation timed out",
+            DekoError::OutOfMemory => "out of memory",
+            DekoError::NotImplemented => "not implemented",
+            DekoError::MisalignedSize => "misaligned size",
+            DekoError::MisalignedData => "misaligned data",
+            DekoError::BadInput => "bad input",
+            DekoError::BadFlags => "bad flags",
+            DekoError::BadState => "bad state",
+            DekoError::Unknown => "unknown error",
+        })
+    }
+}
+
+impl std::error::Error for DekoError {}
+
 trait DkResultExt {
     fn into_result(self) -> Result;
 }
@@ -492,6 +558,87 @@ impl From<DkImageFormat> for ImageFormat {
     }
 }
 
+impl ImageFormat {
+    pub fn is_uint(self) -> bool {
+        matches!(
+            self,
+            ImageFormat::R8Uint
+                | ImageFormat::R16Uint
+                | ImageFormat::R32Uint
+                | ImageFormat::RG8Uint
+                | ImageFormat::RG16Uint
+                | ImageFormat::RG32Uint
+                | ImageFormat::RGB32Uint
+                | ImageFormat::RGBA8Uint
+                | ImageFormat::RGBA16Uint
+                | ImageFormat::RGBA32Uint
+                | ImageFormat::RGB10A2Uint
+                | ImageFormat::RGBX8Uint
+                | ImageFormat::RGBX16Uint
+                | ImageFormat::RGBX32Uint
+        )
+    }
+
+    pub fn is_sint(self) -> bool {
+        matches!(
+            self,
+            ImageFormat::R8Sint
+                | ImageFormat::R16Sint
+                | ImageFormat::R32Sint
+                | ImageFormat::RG8Sint
+                | ImageFormat::RG16Sint
+                | ImageFormat::RG32Sint
+                | ImageFormat::RGB32Sint
+                | ImageFormat::RGBA8Sint
+                | ImageFormat::RGBA16Sint
+                | ImageFormat::RGBA32Sint
+                | ImageFormat::RGBX8Sint
+                | ImageFormat::RGBX16Sint
+                | ImageFormat::RGBX32Sint
+        )
+    }
+
+    pub fn is_depth_stencil(self) -> bool {
+        matches!(
+            self,
+            ImageFormat::S8
+                | ImageFormat::Z16
+                | ImageFormat::Z24X8
+                | ImageFormat::ZF32
+                | ImageFormat::Z24S8
+                | ImageFormat::ZF32X24S8
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearColor {
+    Float([f32; 4]),
+    Uint([u32; 4]),
+    Sint([i32; 4]),
+}
+
+impl ClearColor {
+    /// Picks the variant matching `format`, converting `rgba` to integers for integer formats.
+    pub fn for_format(format: ImageFormat, rgba: [f64; 4]) -> Self {
+        if format.is_uint() {
+            ClearColor::Uint(rgba.map(|c| c as u32))
+        } else if format.is_sint() {
+            ClearColor::Sint(rgba.map(|c| c as i32))
+        } else {
+            ClearColor::Float(rgba.map(|c| c as f32))
+        }
+    }
+
+    pub fn matches_format(&self, format: ImageFormat) -> bool {
+        match self {
+            ClearColor::Float(_) => !format.is_uint() && !format.is_sint() && !format.is_depth_stencil(),
+            ClearColor::Uint(_) => format.is_uint(),
+            ClearColor::Sint(_) => format.is_sint(),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ImageSwizzle {
@@ -505,6 +652,7 @@ pub enum ImageSwizzle {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MsMode {
     Ms1x = DkMsMode_DkMsMode_1x as _,
     Ms2x = DkMsMode_DkMsMode_2x as _,
@@ -593,21 +741,45 @@ impl From<DkStage> for Stage {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     Nearest = DkFilter_DkFilter_Nearest as _,
     Linear = DkFilter_DkFilter_Linear as _,
 }
 
+impl From<DkFilter> for Filter {
+    fn from(val: DkFilter) -> Self {
+        match val {
+            DkFilter_DkFilter_Nearest => Filter::Nearest,
+            DkFilter_DkFilter_Linear => Filter::Linear,
+            _ => unreachable!("Invalid DkFilter"),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MipFilter {
     None = DkMipFilter_DkMipFilter_None as _,
     Nearest = DkMipFilter_DkMipFilter_Nearest as _,
     Linear = DkMipFilter_DkMipFilter_Linear as _,
 }
 
+impl From<DkMipFilter> for MipFilter {
+    fn from(val: DkMipFilter) -> Self {
+        match val {
+            DkMipFilter_DkMipFilter_None => MipFilter::None,
+            DkMipFilter_DkMipFilter_Nearest => MipFilter::Nearest,
+            DkMipFilter_DkMipFilter_Linear => MipFilter::Linear,
+            _ => unreachable!("Invalid DkMipFilter"),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WrapMode {
     Repeat = DkWrapMode_DkWrapMode_Repeat as _,
     MirroredRepeat = DkWrapMode_DkWrapMode_MirroredRepeat as _,
@@ -619,8 +791,25 @@ pub enum WrapMode {
     MirrorClamp = DkWrapMode_DkWrapMode_MirrorClamp as _,
 }
 
+impl From<DkWrapMode> for WrapMode {
+    fn from(val: DkWrapMode) -> Self {
+        match val {
+            DkWrapMode_DkWrapMode_Repeat => WrapMode::Repeat,
+            DkWrapMode_DkWrapMode_MirroredRepeat => WrapMode::MirroredRepeat,
+            DkWrapMode_DkWrapMode_ClampToEdge => WrapMode::ClampToEdge,
+            DkWrapMode_DkWrapMode_ClampToBorder => WrapMode::ClampToBorder,
+            DkWrapMode_DkWrapMode_Clamp => WrapMode::Clamp,
+            DkWrapMode_DkWrapMode_MirrorClampToEdge => WrapMode::MirrorClampToEdge,
+            DkWrapMode_DkWrapMode_MirrorClampToBorder => WrapMode::MirrorClampToBorder,
+            DkWrapMode_DkWrapMode_MirrorClamp => WrapMode::MirrorClamp,
+            _ => unreachable!("Invalid DkWrapMode"),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CompareOp {
     Never = DkCompareOp_DkCompareOp_Never as _,
     Less = DkCompareOp_DkCompareOp_Less as _,
@@ -650,12 +839,24 @@ impl From<DkCompareOp> for CompareOp {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SamplerReduction {
     WeightedAverage = DkSamplerReduction_DkSamplerReduction_WeightedAverage as _,
     Min = DkSamplerReduction_DkSamplerReduction_Min as _,
     Max = DkSamplerReduction_DkSamplerReduction_Max as _,
 }
 
+impl From<DkSamplerReduction> for SamplerReduction {
+    fn from(val: DkSamplerReduction) -> Self {
+        match val {
+            DkSamplerReduction_DkSamplerReduction_WeightedAverage => SamplerReduction::WeightedAverage,
+            DkSamplerReduction_DkSamplerReduction_Min => SamplerReduction::Min,
+            DkSamplerReduction_DkSamplerReduction_Max => SamplerReduction::Max,
+            _ => unreachable!("Invalid DkSamplerReduction"),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum StageFlag {
@@ -668,7 +869,6 @@ pub enum StageFlag {
     GraphicsMask = DkStageFlag_GraphicsMask as _,
 }
 
-pub type CmdList = DkCmdList;
 pub type GpuAddr = DkGpuAddr;
 pub type ResHandle = DkResHandle;
 pub type BufExtents = DkBufExtents;
@@ -708,18 +908,34 @@ pub struct ViewportSwizzle {
 
 pub type Scissor = DkScissor;
 
+#[derive(Default)]
+struct DeviceState {
+    deleter: OnceLock<Arc<DeferredDeleter>>,
+}
+
 #[repr(C)]
-pub struct Device(DkDevice);
+pub struct Device(DkDevice, DeviceState);
+
+// The device only hands out objects and serializes its own internal allocations.
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
 
 impl Drop for Device {
     fn drop(&mut self) {
+        if let Some(deleter) = self.1.deleter.get() {
+            if cfg!(debug_assertions) {
+                deleter.report_leaks();
+            }
+            deleter.flush();
+        }
+
         unsafe { dkDeviceDestroy(self.0) }
     }
 }
 
 impl Device {
     pub unsafe fn from_raw(device: DkDevice) -> Self {
-        Self(device)
+        Self(device, DeviceState::default())
     }
 
     pub fn into_raw(&self) -> DkDevice {
@@ -735,18 +951,34 @@ impl Device {
     }
 }
 
+pub const fn timestamp_to_ns(ts: u64) -> u64 {
+    (ts * 625) / 384
+}
+
+pub const fn ns_to_timestamp(ns: u64) -> u64 {
+    (ns * 384) / 625
+}
+
+pub(crate) fn align_up(value: u32, alignment: u32) -> u32 {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
 #[repr(C)]
-pub struct MemBlock(DkMemBlock);
+pub struct MemBlock(DkMemBlock, LastUse);
+
+// Memory blocks are immutable after creation; their contents are synchronized by the user.
+unsafe impl Send for MemBlock {}
+unsafe impl Sync for MemBlock {}
 
 impl Drop for MemBlock {
     fn drop(&mut self) {
-        unsafe { dkMemBlockDestroy(self.0) }
+        self.1.destroy::<Self, _>(self.0, dkMemBlockDestroy)
     }
 }
 
 impl MemBlock {
     pub unsafe fn from_raw(mem_block: DkMemBlock) -> Self {
-        Self(mem_block)
+        Self(mem_block, LastUse::default())
     }
 
     pub fn into_raw(self) -> DkMemBlock {
@@ -771,17 +1003,21 @@ impl MemBlock {
 }
 
 #[repr(C)]
-pub struct Fence(DkFence);
+pub struct Fence(DkFence, Option<Arc<Mutex<sync::WakerSlot>>>);
 
 impl Fence {
     pub unsafe fn from_raw(fence: DkFence) -> Self {
-        Self(fence)
+        Self(fence, None)
     }
 
     pub fn into_raw(self) -> DkFence {
         self.0
     }
 
+    pub fn new() -> Self {
+        Self(unsafe { std::mem::zeroed() }, None)
+    }
+
     pub fn wait(&mut self, timeout: i64) -> Result {
         unsafe { dkFenceWait(&mut self.0, timeout).into_result() }
     }
@@ -789,6 +1025,10 @@ impl Fence {
     pub fn wait_no_timeout(&mut self) -> Result {
         self.wait(-1)
     }
+
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result {
+        self.wait(timeout.as_nanos().min(i64::MAX as u128) as i64)
+    }
 }
 
 #[repr(C)]
@@ -816,39 +1056,81 @@ impl Variable {
     }
 }
 
+#[derive(Default)]
+struct CmdBufState<'a> {
+    primitive_restart: Cell<Option<u32>>,
+    color_formats: Cell<[Option<ImageFormat>; DK_MAX_RENDER_TARGETS as usize]>,
+    // Keeps the pool, and the chunks it handed to this command buffer, alive.
+    mem_pool: Option<CmdMemPoolUser<'a>>,
+    memory: Vec<&'a MemBlock>,
+    last_use: LastUse,
+}
+
+impl CmdBufState<'_> {
+    // Everything a submission of one of the buffer's lists uses.
+    fn get_uses(&self) -> impl Iterator<Item = &LastUse> {
+        std::iter::once(&self.last_use).chain(self.memory.iter().map(|mem| &mem.1))
+    }
+}
+
+/// A command buffer borrowing its device and the memory blocks added to it for `'a`.
 #[repr(C)]
-pub struct CmdBuf(DkCmdBuf);
+pub struct CmdBuf<'a>(DkCmdBuf, CmdBufState<'a>);
+
+// Recording mutates the command buffer through `&self`, so it may move between threads but
+// never be shared between them.
+unsafe impl Send for CmdBuf<'_> {}
 
-impl Drop for CmdBuf {
+impl Drop for CmdBuf<'_> {
     fn drop(&mut self) {
-        unsafe { dkCmdBufDestroy(self.0) }
+        self.1.last_use.destroy::<Self, _>(self.0, dkCmdBufDestroy)
     }
 }
 
-impl CmdBuf {
+impl<'a> CmdBuf<'a> {
     pub unsafe fn from_raw(cmd_buf: DkCmdBuf) -> Self {
-        Self(cmd_buf)
+        Self(cmd_buf, CmdBufState::default())
     }
 
     pub fn into_raw(self) -> DkCmdBuf {
         self.0
     }
 
-    pub fn add_memory(&self, mem: &MemBlock, offset: u32, size: u32) {
-        unsafe { dkCmdBufAddMemory(self.0, mem.0, offset, size) }
+    /// Takes `&mut self` so that `'a` can't be shortened through a shared borrow to fit `mem`.
+    pub fn add_memory(&mut self, mem: &'a MemBlock, offset: u32, size: u32) {
+        unsafe { self.add_memory_unchecked(mem, offset, size) }
+        self.1.memory.push(mem);
     }
 
-    pub fn finish_list(&self) -> DkCmdList {
-        unsafe { dkCmdBufFinishList(self.0) }
+    /// Adds memory without tying it to `'a`, for owners that keep `mem` alive longer than `self`.
+    pub(crate) unsafe fn add_memory_unchecked(&self, mem: &MemBlock, offset: u32, size: u32) {
+        dkCmdBufAddMemory(self.0, mem.0, offset, size)
     }
 
-    pub fn clear(&self) {
+    pub fn finish_list(&self) -> CmdList<'_> {
+        CmdList(unsafe { dkCmdBufFinishList(self.0) }, Some(&self.1))
+    }
+
+    pub fn record<F: FnOnce(&Self)>(&self, f: F) -> CmdList<'_> {
+        f(self);
+        self.finish_list()
+    }
+
+    pub fn clear(&mut self) {
         unsafe { dkCmdBufClear(self.0) }
+
+        self.1.memory.clear();
+        self.1.primitive_restart.set(None);
+        self.1.color_formats.set(Default::default());
+    }
+
+    pub(crate) fn uses_memory_pool(&self, pool: &CmdMemPool) -> bool {
+        self.1.mem_pool.as_ref().is_some_and(|user| user.get_pool().user_data() == pool.user_data())
     }
 
-    pub fn begin_capture_cmds(&self, storage: &mut [u32]) {
+    pub unsafe fn begin_capture_cmds(&self, storage: &mut [u32]) {
         assert!(storage.len() <= u32::MAX as usize);
-        unsafe { dkCmdBufBeginCaptureCmds(self.0, storage.as_mut_ptr(), storage.len() as u32) }
+        dkCmdBufBeginCaptureCmds(self.0, storage.as_mut_ptr(), storage.len() as u32)
     }
 
     pub fn end_capture_cmds(&self) -> u32 {
@@ -859,8 +1141,9 @@ impl CmdBuf {
         unsafe { dkCmdBufReplayCmds(self.0, words.as_ptr(), words.len() as _) }
     }
 
-    pub fn call_list(&self, list: DkCmdList) {
-        unsafe { dkCmdBufCallList(self.0, list) }
+    /// Takes `&mut self` for the same reason as [`CmdBuf::add_memory`]: the called list must stay valid for all of `'a`.
+    pub fn call_list(&mut self, list: CmdList<'a>) {
+        unsafe { dkCmdBufCallList(self.0, list.0) }
     }
 
     pub fn wait_fence(&self, fence: &mut Fence) {
@@ -896,6 +1179,10 @@ impl CmdBuf {
         unsafe { dkCmdBufBindUniformBuffers(self.0, stage as _, first_id, buffers.as_ptr(), buffers.len() as u32) }
     }
 
+    pub fn bind_uniform<T: Std140>(&self, stage: Stage, id: u32, ubo: &UniformBuffer<T>) {
+        self.bind_uniform_buffer(stage, id, ubo.get_gpu_addr(), ubo.get_size())
+    }
+
     pub fn bind_storage_buffer(&self, stage: Stage, id: u32, buf_addr: DkGpuAddr, buf_size: u32) {
         let buf_extents = [DkBufExtents { addr: buf_addr, size: buf_size }];
         self.bind_storage_buffers(stage, id, &buf_extents);
@@ -922,6 +1209,12 @@ impl CmdBuf {
     }
 
     pub fn bind_render_targets(&self, color_targets: &[&ImageView], depth_target: Option<&ImageView>) {
+        let mut formats = [None; DK_MAX_RENDER_TARGETS as usize];
+        for (format, view) in formats.iter_mut().zip(color_targets) {
+            *format = view.get_format();
+        }
+        self.1.color_formats.set(formats);
+
         unsafe {
             dkCmdBufBindRenderTargets(
                 self.0,
@@ -977,6 +1270,10 @@ impl CmdBuf {
         unsafe { dkCmdBufBindIdxBuffer(self.0, format as _, address) }
     }
 
+    pub fn bind_index_buffer<T: Index>(&self, buffer: &IndexBuffer<T>) {
+        self.bind_idx_buffer(T::FORMAT, buffer.get_gpu_addr())
+    }
+
     pub fn set_viewports(&self, first_id: u32, viewports: &[Viewport]) {
         unsafe { dkCmdBufSetViewports(self.0, first_id, viewports.as_ptr() as *const _, viewports.len() as u32) }
     }
@@ -1046,6 +1343,7 @@ impl CmdBuf {
     }
 
     pub fn set_primitive_restart(&self, enable: bool, index: u32) {
+        self.1.primitive_restart.set(if enable { Some(index) } else { None });
         unsafe { dkCmdBufSetPrimitiveRestart(self.0, enable, index) }
     }
 
@@ -1074,6 +1372,47 @@ impl CmdBuf {
         unsafe { dkCmdBufClearColor(self.0, target, clear_mask.bits, data.as_ptr() as *const _) }
     }
 
+    pub fn clear_color_uint(&self, target: u32, clear_mask: ColorMask, r: u32, g: u32, b: u32, a: u32) {
+        let data = [r, g, b, a];
+        unsafe { dkCmdBufClearColor(self.0, target, clear_mask.bits, data.as_ptr() as *const _) }
+    }
+
+    pub fn clear_color_sint(&self, target: u32, clear_mask: ColorMask, r: i32, g: i32, b: i32, a: i32) {
+        let data = [r, g, b, a];
+        unsafe { dkCmdBufClearColor(self.0, target, clear_mask.bits, data.as_ptr() as *const _) }
+    }
+
+    /// Clears a color target, checking in debug builds that the value type matches the format
+    /// of the bound target.
+    pub fn clear_color(&self, target: u32, clear_mask: ColorMask, color: ClearColor) {
+        if let Some(format) = self.get_target_format(target) {
+            debug_assert!(
+                color.matches_format(format),
+                "{:?} does not match the format {:?} of render target {}",
+                color,
+                format,
+                target
+            );
+        }
+
+        match color {
+            ClearColor::Float([r, g, b, a]) => self.clear_color_float(target, clear_mask, r, g, b, a),
+            ClearColor::Uint([r, g, b, a]) => self.clear_color_uint(target, clear_mask, r, g, b, a),
+            ClearColor::Sint([r, g, b, a]) => self.clear_color_sint(target, clear_mask, r, g, b, a),
+        }
+    }
+
+    /// Clears a color target with `rgba` converted to the value type of the bound target's format.
+    /// Targets of unknown format are cleared with float values.
+    pub fn clear_color_rgba(&self, target: u32, clear_mask: ColorMask, rgba: [f64; 4]) {
+        let format = self.get_target_format(target).unwrap_or(ImageFormat::None);
+        self.clear_color(target, clear_mask, ClearColor::for_format(format, rgba));
+    }
+
+    fn get_target_format(&self, target: u32) -> Option<ImageFormat> {
+        self.1.color_formats.get().get(target as usize).copied().flatten()
+    }
+
     pub fn clear_depth_stencil(&self, clear_depth: bool, depth_value: f32, stencil_mask: u8, stencil_value: u8) {
         unsafe { dkCmdBufClearDepthStencil(self.0, clear_depth, depth_value, stencil_mask, stencil_value) }
     }
@@ -1120,6 +1459,38 @@ impl CmdBuf {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_indexed_from<T: Index>(
+        &self,
+        prim: Primitive,
+        buffer: &IndexBuffer<T>,
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) {
+        debug_assert!(
+            first_index as u64 + index_count as u64 <= buffer.len() as u64,
+            "indices {}..{} are out of bounds for an index buffer of length {}",
+            first_index,
+            first_index as u64 + index_count as u64,
+            buffer.len()
+        );
+
+        if let Some(restart_index) = self.1.primitive_restart.get() {
+            debug_assert!(
+                restart_index <= T::MAX,
+                "primitive restart index {:#x} can never occur in a {:?} index buffer",
+                restart_index,
+                T::FORMAT
+            );
+        }
+
+        self.bind_index_buffer(buffer);
+        self.draw_indexed(prim, index_count, instance_count, first_index, vertex_offset, first_instance);
+    }
+
     pub fn draw_indexed_indirect(&self, prim: Primitive, indirect: GpuAddr) {
         unsafe { dkCmdBufDrawIndexedIndirect(self.0, prim as _, indirect) }
     }
@@ -1132,6 +1503,47 @@ impl CmdBuf {
         unsafe { dkCmdBufDispatchComputeIndirect(self.0, indirect) }
     }
 
+    pub fn draw_indirect_from(&self, prim: Primitive, buffer: &IndirectBuffer<DrawIndirectArgs>, index: u32) {
+        self.draw_indirect(prim, buffer.get_entry_addr(index))
+    }
+
+    pub fn draw_indexed_indirect_from(
+        &self,
+        prim: Primitive,
+        buffer: &IndirectBuffer<DrawIndexedIndirectArgs>,
+        index: u32,
+    ) {
+        self.draw_indexed_indirect(prim, buffer.get_entry_addr(index))
+    }
+
+    pub fn dispatch_compute_indirect_from(&self, buffer: &IndirectBuffer<DispatchIndirectArgs>, index: u32) {
+        self.dispatch_compute_indirect(buffer.get_entry_addr(index))
+    }
+
+    pub fn multi_draw_indirect(
+        &self,
+        prim: Primitive,
+        buffer: &IndirectBuffer<DrawIndirectArgs>,
+        first: u32,
+        count: u32,
+    ) {
+        for index in first..first + count {
+            self.draw_indirect_from(prim, buffer, index);
+        }
+    }
+
+    pub fn multi_draw_indexed_indirect(
+        &self,
+        prim: Primitive,
+        buffer: &IndirectBuffer<DrawIndexedIndirectArgs>,
+        first: u32,
+        count: u32,
+    ) {
+        for index in first..first + count {
+            self.draw_indexed_indirect_from(prim, buffer, index);
+        }
+    }
+
     pub fn push_constants(
         &self,
         ubo_addr: GpuAddr,
@@ -1143,6 +1555,13 @@ impl CmdBuf {
         unsafe { dkCmdBufPushConstants(self.0, ubo_addr, ubo_size, offset, size, data) }
     }
 
+    pub fn push_uniform<T: Std140>(&self, ubo: &UniformBuffer<T>, value: &T) {
+        let size = std::mem::size_of::<T>() as u32;
+        debug_assert_eq!(size % 4, 0, "uniform data size must be a multiple of 4 bytes");
+
+        self.push_constants(ubo.get_gpu_addr(), ubo.get_size(), 0, size, value as *const T as *const _)
+    }
+
     pub fn push_data(&self, addr: GpuAddr, data: *const std::ffi::c_void, size: u32) {
         unsafe { dkCmdBufPushData(self.0, addr, data, size) }
     }
@@ -1199,8 +1618,55 @@ impl CmdBuf {
     }
 }
 
+/// A finished command list, borrowing the command buffer it was recorded into and thereby its memory.
+///
+/// Lists compare and hash by their handle alone.
+#[derive(Copy, Clone)]
+pub struct CmdList<'a>(DkCmdList, Option<&'a CmdBufState<'a>>);
+
+// A finished list is an immutable handle into command memory. Of its command buffer, submission only
+// reads the memory list, which can't change while the list borrows the buffer, and the mutex-guarded
+// last use.
+unsafe impl Send for CmdList<'_> {}
+unsafe impl Sync for CmdList<'_> {}
+
+impl std::fmt::Debug for CmdList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CmdList").field(&self.0).finish()
+    }
+}
+
+impl PartialEq for CmdList<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for CmdList<'_> {}
+
+impl std::hash::Hash for CmdList<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<'a> CmdList<'a> {
+    pub unsafe fn from_raw(list: DkCmdList) -> Self {
+        Self(list, None)
+    }
+
+    pub fn into_raw(self) -> DkCmdList {
+        self.0
+    }
+}
+
+/// A queue, holding its device's deleter when created from a [`QueueMaker`] so that resources it uses
+/// are destroyed only once that use has completed.
 #[repr(C)]
-pub struct Queue(DkQueue);
+pub struct Queue(DkQueue, Option<Arc<DeferredDeleter>>);
+
+// Submissions are not internally synchronized; share a queue between threads through a `Mutex`.
+unsafe impl Send for Queue {}
 
 impl Drop for Queue {
     fn drop(&mut self) {
@@ -1210,7 +1676,7 @@ impl Drop for Queue {
 
 impl Queue {
     pub unsafe fn from_raw(queue: DkQueue) -> Self {
-        Self(queue)
+        Self(queue, None)
     }
 
     pub fn into_raw(self) -> DkQueue {
@@ -1229,8 +1695,31 @@ impl Queue {
         unsafe { dkQueueSignalFence(self.0, &mut fence.0, flush) }
     }
 
-    pub fn submit_commands(&self, cmds: DkCmdList) {
-        unsafe { dkQueueSubmitCommands(self.0, cmds) }
+    pub fn submit_commands(&self, cmds: CmdList) {
+        unsafe { dkQueueSubmitCommands(self.0, cmds.0) }
+
+        if let Some(state) = cmds.1 {
+            self.record_use(state.get_uses());
+        }
+    }
+
+    /// Records that the work submitted so far uses `mem`, so dropping it is deferred until that work
+    /// has completed.
+    pub fn mark_used(&self, mem: &MemBlock) {
+        self.record_use(std::iter::once(&mem.1));
+    }
+
+    fn record_use<'r>(&self, uses: impl IntoIterator<Item = &'r LastUse>) {
+        let Some(deleter) = &self.1 else {
+            return;
+        };
+
+        let mut fence = Fence::new();
+        self.signal_fence(&mut fence, false);
+
+        for last_use in uses {
+            last_use.record(fence.0, deleter);
+        }
     }
 
     pub fn flush(&self) {
@@ -1247,6 +1736,8 @@ impl Queue {
 
     pub fn present_image(&self, swapchain: &Swapchain, image_slot: i32) {
         unsafe { dkQueuePresentImage(self.0, swapchain.0, image_slot) }
+
+        self.record_use(std::iter::once(&swapchain.1));
     }
 }
 
@@ -1275,12 +1766,28 @@ impl Shader {
     }
 }
 
+// Properties of an initialized layout that deko3d doesn't let us query back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct ImageLayoutInfo {
+    type_: ImageType,
+    format: ImageFormat,
+    dimensions: (u32, u32, u32),
+    mip_levels: u32,
+}
+
+impl Default for ImageLayoutInfo {
+    fn default() -> Self {
+        ImageLayoutInfo { type_: ImageType::None, format: ImageFormat::None, dimensions: (0, 0, 0), mip_levels: 0 }
+    }
+}
+
 #[repr(C)]
-pub struct ImageLayout(Pin<Box<DkImageLayout>>);
+pub struct ImageLayout(Pin<Box<DkImageLayout>>, ImageLayoutInfo);
 
 impl ImageLayout {
+    /// Layouts created from a raw handle report no type, format, dimensions or mip levels.
     pub unsafe fn from_raw(layout: DkImageLayout) -> Self {
-        Self(Box::pin(layout))
+        Self(Box::pin(layout), ImageLayoutInfo::default())
     }
 
     pub fn into_raw(self) -> DkImageLayout {
@@ -1288,7 +1795,7 @@ impl ImageLayout {
     }
 
     pub fn new() -> Self {
-        unsafe { Self(Box::pin(std::mem::zeroed())) }
+        unsafe { Self(Box::pin(std::mem::zeroed()), ImageLayoutInfo::default()) }
     }
 
     pub fn get_size(&self) -> u64 {
@@ -1298,14 +1805,31 @@ impl ImageLayout {
     pub fn get_alignment(&self) -> u32 {
         unsafe { dkImageLayoutGetAlignment(self.0.as_ref().get_ref()) }
     }
+
+    pub fn get_type(&self) -> ImageType {
+        self.1.type_
+    }
+
+    pub fn get_format(&self) -> ImageFormat {
+        self.1.format
+    }
+
+    pub fn get_dimensions(&self) -> (u32, u32, u32) {
+        self.1.dimensions
+    }
+
+    pub fn get_mip_levels(&self) -> u32 {
+        self.1.mip_levels
+    }
 }
 
 #[repr(C)]
-pub struct Image(Pin<Box<DkImage>>);
+pub struct Image(Pin<Box<DkImage>>, ImageLayoutInfo);
 
 impl Image {
+    /// Images created from a raw handle report no type, format, dimensions or mip levels.
     pub unsafe fn from_raw(image: DkImage) -> Self {
-        Self(Box::pin(image))
+        Self(Box::pin(image), ImageLayoutInfo::default())
     }
 
     pub fn into_raw(self) -> DkImage {
@@ -1313,11 +1837,12 @@ impl Image {
     }
 
     pub fn new() -> Self {
-        unsafe { Self(Box::pin(std::mem::zeroed())) }
+        unsafe { Self(Box::pin(std::mem::zeroed()), ImageLayoutInfo::default()) }
     }
 
     pub fn initialize(&mut self, layout: &ImageLayout, mem: &MemBlock, offset: u32) {
         unsafe { dkImageInitialize(self.0.as_mut().get_mut(), layout.0.as_ref().get_ref(), mem.0, offset) }
+        self.1 = layout.1;
     }
 
     pub fn get_gpu_addr(&self) -> DkGpuAddr {
@@ -1325,25 +1850,28 @@ impl Image {
     }
 
     pub fn get_layout<'a: 'b, 'b>(&'a self) -> &'b ImageLayout {
-        // We're assuming that Image and ImageLayout have the same memory layout.
+        // We're assuming that Image and ImageLayout have the same memory layout: DkImage starts with its
+        // DkImageLayout, and both carry the same ImageLayoutInfo after it.
         unsafe { &*(self as *const Image as *const ImageLayout) }
     }
 }
 
 #[repr(C)]
-pub struct Swapchain(DkSwapchain);
+pub struct Swapchain(DkSwapchain, LastUse);
+
+// The swapchain isn't tied to the thread that created it, but acquiring images and changing the crop or
+// swap interval are not synchronized, so it is not shared between threads.
+unsafe impl Send for Swapchain {}
 
 impl Drop for Swapchain {
     fn drop(&mut self) {
-        unsafe {
-            dkSwapchainDestroy(self.0);
-        }
+        self.1.destroy::<Self, _>(self.0, dkSwapchainDestroy)
     }
 }
 
 impl Swapchain {
     pub unsafe fn from_raw(swapchain: DkSwapchain) -> Self {
-        Self(swapchain)
+        Self(swapchain, LastUse::default())
     }
 
     pub fn into_raw(self) -> DkSwapchain {
@@ -1431,7 +1959,7 @@ impl DeviceMaker {
     }
 
     pub fn create(&self) -> Device {
-        unsafe { Device(dkDeviceCreate(&self.0)) }
+        unsafe { Device(dkDeviceCreate(&self.0), DeviceState::default()) }
     }
 }
 
@@ -1479,23 +2007,23 @@ impl MemBlockMaker {
     }
 
     pub fn create(&self) -> MemBlock {
-        unsafe { MemBlock(dkMemBlockCreate(&self.0)) }
+        unsafe { MemBlock(dkMemBlockCreate(&self.0), LastUse::default()) }
     }
 }
 
 #[repr(C)]
-pub struct CmdBufMaker(DkCmdBufMaker);
+pub struct CmdBufMaker<'a>(DkCmdBufMaker, Option<CmdMemPool<'a>>, PhantomData<&'a Device>);
 
-impl CmdBufMaker {
+impl<'a> CmdBufMaker<'a> {
     pub unsafe fn from_raw(maker: DkCmdBufMaker) -> Self {
-        Self(maker)
+        Self(maker, None, PhantomData)
     }
 
     pub fn into_raw(self) -> DkCmdBufMaker {
         self.0
     }
 
-    pub fn new(device: &Device) -> Self {
+    pub fn new(device: &'a Device) -> Self {
         unsafe {
             let mut maker: DkCmdBufMaker = std::mem::zeroed();
 
@@ -1503,22 +2031,36 @@ impl CmdBufMaker {
             maker.userData = std::ptr::null_mut();
             maker.cbAddMem = None;
 
-            CmdBufMaker(maker)
+            CmdBufMaker(maker, None, PhantomData)
         }
     }
 
     pub fn set_user_data(&mut self, user_data: *mut std::ffi::c_void) -> &mut Self {
+        if self.1.take().is_some() {
+            self.0.cbAddMem = None;
+        }
+
         self.0.userData = user_data;
         self
     }
 
     pub fn set_add_mem_callback(&mut self, cb_add_mem: DkCmdBufAddMemFunc) -> &mut Self {
         self.0.cbAddMem = cb_add_mem;
+        self.1 = None;
+        self
+    }
+
+    pub fn with_memory_pool(&mut self, pool: &CmdMemPool<'a>) -> &mut Self {
+        self.0.userData = pool.user_data();
+        self.0.cbAddMem = Some(cmd_mem_pool_add_mem);
+        self.1 = Some(pool.clone());
         self
     }
 
-    pub fn create(&self) -> CmdBuf {
-        unsafe { CmdBuf(dkCmdBufCreate(&self.0)) }
+    pub fn create(&self) -> CmdBuf<'a> {
+        let state = CmdBufState { mem_pool: self.1.as_ref().map(CmdMemPool::attach), ..Default::default() };
+
+        unsafe { CmdBuf(dkCmdBufCreate(&self.0), state) }
     }
 }
 
@@ -1536,11 +2078,11 @@ bitflags! {
 }
 
 #[repr(C)]
-pub struct QueueMaker(DkQueueMaker);
+pub struct QueueMaker(DkQueueMaker, Option<Arc<DeferredDeleter>>);
 
 impl QueueMaker {
     pub unsafe fn from_raw(maker: DkQueueMaker) -> Self {
-        Self(maker)
+        Self(maker, None)
     }
 
     pub fn into_raw(self) -> DkQueueMaker {
@@ -1559,7 +2101,7 @@ impl QueueMaker {
             maker.perWarpScratchMemorySize = 4 * DK_PER_WARP_SCRATCH_MEM_ALIGNMENT;
             maker.maxConcurrentComputeJobs = DK_DEFAULT_MAX_COMPUTE_CONCURRENT_JOBS;
 
-            QueueMaker(maker)
+            QueueMaker(maker, Some(device.get_shared_deleter().clone()))
         }
     }
 
@@ -1609,7 +2151,7 @@ impl QueueMaker {
     }
 
     pub fn create(&self) -> Queue {
-        unsafe { Queue(dkQueueCreate(&self.0)) }
+        unsafe { Queue(dkQueueCreate(&self.0), self.1.clone()) }
     }
 }
 
@@ -1764,17 +2306,26 @@ impl ImageLayoutMaker {
         unsafe {
             dkImageLayoutInitialize(obj.0.as_mut().get_mut(), &self.0);
         }
+
+        obj.1 = ImageLayoutInfo {
+            type_: self.get_type(),
+            format: self.get_format(),
+            dimensions: self.get_dimensions(),
+            mip_levels: self.get_mip_levels(),
+        };
     }
 }
 
-pub struct ImageView<'a>(DkImageView, PhantomData<&'a Image>);
+// Render targets are bound as arrays of `&ImageView` cast to `DkImageView` pointers.
+#[repr(C)]
+pub struct ImageView<'a>(DkImageView, &'a Image);
 
 impl<'a> ImageView<'a> {
     pub fn into_raw(self) -> DkImageView {
         self.0
     }
 
-    pub fn new(image: &Image) -> ImageView {
+    pub fn new(image: &'a Image) -> Self {
         ImageView(
             DkImageView {
                 pImage: image.0.as_ref().get_ref(),
@@ -1792,9 +2343,58 @@ impl<'a> ImageView<'a> {
                 mipLevelOffset: 0,
                 mipLevelCount: 0,
             },
-            PhantomData,
+            image,
         )
     }
+
+    pub fn set_type(&mut self, type_: ImageType) -> &mut Self {
+        self.0.type_ = type_ as _;
+        self
+    }
+
+    pub fn set_format(&mut self, format: ImageFormat) -> &mut Self {
+        self.0.format = format as _;
+        self
+    }
+
+    pub fn set_layers(&mut self, offset: u32, count: u32) -> &mut Self {
+        self.0.layerOffset = offset as _;
+        self.0.layerCount = count as _;
+        self
+    }
+
+    pub fn set_mip_levels(&mut self, offset: u32, count: u32) -> &mut Self {
+        self.0.mipLevelOffset = offset as _;
+        self.0.mipLevelCount = count as _;
+        self
+    }
+
+    pub fn get_image(&self) -> &'a Image {
+        self.1
+    }
+
+    /// Width and height of the first mip level the view covers, or `None` for images created from raw
+    /// handles.
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        let (width, height, _) = self.1.get_layout().get_dimensions();
+        if width == 0 {
+            return None;
+        }
+
+        let level = self.0.mipLevelOffset as u32;
+        Some(((width >> level).max(1), (height >> level).max(1)))
+    }
+
+    /// The format the view reinterprets the image as, or else the format of the image itself.
+    pub fn get_format(&self) -> Option<ImageFormat> {
+        match self.0.format {
+            DkImageFormat_DkImageFormat_None => match self.1.get_layout().get_format() {
+                ImageFormat::None => None,
+                format => Some(format),
+            },
+            format => Some(format.into()),
+        }
+    }
 }
 
 #[repr(C)]
@@ -1811,32 +2411,47 @@ impl ImageDescriptor {
     }
 }
 
+// The border color is a union, so remember which member was written for serialization.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BorderColorType {
+    Float,
+    Uint,
+    Sint,
+}
+
 #[repr(C)]
-pub struct Sampler(DkSampler);
+pub struct Sampler(DkSampler, BorderColorType);
 
 impl Sampler {
     pub fn new() -> Self {
         unsafe {
-            Sampler(DkSampler {
-                minFilter: DkFilter_DkFilter_Nearest,
-                magFilter: DkFilter_DkFilter_Nearest,
-                mipFilter: DkMipFilter_DkMipFilter_None,
-                wrapMode: [DkWrapMode_DkWrapMode_Repeat, DkWrapMode_DkWrapMode_Repeat, DkWrapMode_DkWrapMode_Repeat],
-                lodClampMin: 0.0,
-                lodClampMax: 1000.0,
-                lodBias: 0.0,
-                lodSnap: 0.0,
-                compareEnable: false,
-                compareOp: DkCompareOp_DkCompareOp_Less,
-                borderColor: [
-                    DkSampler__bindgen_ty_1 { value_ui: 0 },
-                    DkSampler__bindgen_ty_1 { value_ui: 0 },
-                    DkSampler__bindgen_ty_1 { value_ui: 0 },
-                    DkSampler__bindgen_ty_1 { value_ui: 0 },
-                ],
-                maxAnisotropy: 1.0,
-                reductionMode: DkSamplerReduction_DkSamplerReduction_WeightedAverage,
-            })
+            Sampler(
+                DkSampler {
+                    minFilter: DkFilter_DkFilter_Nearest,
+                    magFilter: DkFilter_DkFilter_Nearest,
+                    mipFilter: DkMipFilter_DkMipFilter_None,
+                    wrapMode: [
+                        DkWrapMode_DkWrapMode_Repeat,
+                        DkWrapMode_DkWrapMode_Repeat,
+                        DkWrapMode_DkWrapMode_Repeat,
+                    ],
+                    lodClampMin: 0.0,
+                    lodClampMax: 1000.0,
+                    lodBias: 0.0,
+                    lodSnap: 0.0,
+                    compareEnable: false,
+                    compareOp: DkCompareOp_DkCompareOp_Less,
+                    borderColor: [
+                        DkSampler__bindgen_ty_1 { value_ui: 0 },
+                        DkSampler__bindgen_ty_1 { value_ui: 0 },
+                        DkSampler__bindgen_ty_1 { value_ui: 0 },
+                        DkSampler__bindgen_ty_1 { value_ui: 0 },
+                    ],
+                    maxAnisotropy: 1.0,
+                    reductionMode: DkSamplerReduction_DkSamplerReduction_WeightedAverage,
+                },
+                BorderColorType::Float,
+            )
         }
     }
 
@@ -1881,6 +2496,7 @@ impl Sampler {
         self.0.borderColor[1].value_f = g;
         self.0.borderColor[2].value_f = b;
         self.0.borderColor[3].value_f = a;
+        self.1 = BorderColorType::Float;
         self
     }
 
@@ -1889,6 +2505,7 @@ impl Sampler {
         self.0.borderColor[1].value_ui = g;
         self.0.borderColor[2].value_ui = b;
         self.0.borderColor[3].value_ui = a;
+        self.1 = BorderColorType::Uint;
         self
     }
 
@@ -1897,6 +2514,7 @@ impl Sampler {
         self.0.borderColor[1].value_i = g;
         self.0.borderColor[2].value_i = b;
         self.0.borderColor[3].value_i = a;
+        self.1 = BorderColorType::Sint;
         self
     }
 
@@ -1909,6 +2527,83 @@ impl Sampler {
         self.0.reductionMode = mode as _;
         self
     }
+
+    pub fn get_min_filter(&self) -> Filter {
+        Filter::from(self.0.minFilter)
+    }
+
+    pub fn get_mag_filter(&self) -> Filter {
+        Filter::from(self.0.magFilter)
+    }
+
+    pub fn get_mip_filter(&self) -> MipFilter {
+        MipFilter::from(self.0.mipFilter)
+    }
+
+    pub fn get_wrap_mode(&self) -> (WrapMode, WrapMode, WrapMode) {
+        (WrapMode::from(self.0.wrapMode[0]), WrapMode::from(self.0.wrapMode[1]), WrapMode::from(self.0.wrapMode[2]))
+    }
+
+    pub fn get_lod_clamp(&self) -> (f32, f32) {
+        (self.0.lodClampMin, self.0.lodClampMax)
+    }
+
+    pub fn get_lod_bias(&self) -> f32 {
+        self.0.lodBias
+    }
+
+    pub fn get_lod_snap(&self) -> f32 {
+        self.0.lodSnap
+    }
+
+    pub fn get_depth_compare(&self) -> (bool, CompareOp) {
+        (self.0.compareEnable, CompareOp::from(self.0.compareOp))
+    }
+
+    pub fn get_border_color(&self) -> (f32, f32, f32, f32) {
+        unsafe {
+            (
+                self.0.borderColor[0].value_f,
+                self.0.borderColor[1].value_f,
+                self.0.borderColor[2].value_f,
+                self.0.borderColor[3].value_f,
+            )
+        }
+    }
+
+    pub fn get_border_color_ui(&self) -> (u32, u32, u32, u32) {
+        unsafe {
+            (
+                self.0.borderColor[0].value_ui,
+                self.0.borderColor[1].value_ui,
+                self.0.borderColor[2].value_ui,
+                self.0.borderColor[3].value_ui,
+            )
+        }
+    }
+
+    pub fn get_border_color_i(&self) -> (i32, i32, i32, i32) {
+        unsafe {
+            (
+                self.0.borderColor[0].value_i,
+                self.0.borderColor[1].value_i,
+                self.0.borderColor[2].value_i,
+                self.0.borderColor[3].value_i,
+            )
+        }
+    }
+
+    pub fn get_max_anisotropy(&self) -> f32 {
+        self.0.maxAnisotropy
+    }
+
+    pub fn get_reduction_mode(&self) -> SamplerReduction {
+        SamplerReduction::from(self.0.reductionMode)
+    }
+
+    pub(crate) fn get_border_color_type(&self) -> BorderColorType {
+        self.1
+    }
 }
 
 #[repr(C)]
@@ -1926,6 +2621,7 @@ impl SamplerDescriptor {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PolygonMode {
     Point = DkPolygonMode_DkPolygonMode_Point,
     Line = DkPolygonMode_DkPolygonMode_Line,
@@ -1954,6 +2650,7 @@ bitflags! {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Face {
     None = DkFace_DkFace_None,
     Front = DkFace_DkFace_Front,
@@ -1975,6 +2672,7 @@ impl From<DkFace> for Face {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FrontFace {
     CW = DkFrontFace_DkFrontFace_CW,
     CCW = DkFrontFace_DkFrontFace_CCW,
@@ -1992,6 +2690,7 @@ impl From<DkFrontFace> for FrontFace {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProvokingVertex {
     First = DkProvokingVertex_DkProvokingVertex_First,
     Last = DkProvokingVertex_DkProvokingVertex_Last,
@@ -2145,6 +2844,7 @@ impl RasterizerState {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CoverageModulation {
     None = DkCoverageModulation_DkCoverageModulation_None,
     Rgb = DkCoverageModulation_DkCoverageModulation_Rgb,
@@ -2265,6 +2965,7 @@ impl MultisampleState {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogicOp {
     Clear = DkLogicOp_DkLogicOp_Clear,
     And = DkLogicOp_DkLogicOp_And,
@@ -2393,10 +3094,15 @@ impl ColorWriteState {
 
         self
     }
+
+    pub fn get_mask(&self, id: u32) -> u32 {
+        (self.0.masks >> (id * 4)) & 0xF
+    }
 }
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendOp {
     Add = DkBlendOp_DkBlendOp_Add,
     Sub = DkBlendOp_DkBlendOp_Sub,
@@ -2420,6 +3126,7 @@ impl From<DkBlendOp> for BlendOp {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendFactor {
     Zero = DkBlendFactor_DkBlendFactor_Zero,
     One = DkBlendFactor_DkBlendFactor_One,
@@ -2544,10 +3251,35 @@ impl BlendState {
 
         self
     }
+
+    pub fn get_color_blend_op(&self) -> BlendOp {
+        BlendOp::from(self.0.colorBlendOp())
+    }
+
+    pub fn get_src_color_blend_factor(&self) -> BlendFactor {
+        BlendFactor::from(self.0.srcColorBlendFactor())
+    }
+
+    pub fn get_dst_color_blend_factor(&self) -> BlendFactor {
+        BlendFactor::from(self.0.dstColorBlendFactor())
+    }
+
+    pub fn get_alpha_blend_op(&self) -> BlendOp {
+        BlendOp::from(self.0.alphaBlendOp())
+    }
+
+    pub fn get_src_alpha_blend_factor(&self) -> BlendFactor {
+        BlendFactor::from(self.0.srcAlphaBlendFactor())
+    }
+
+    pub fn get_dst_alpha_blend_factor(&self) -> BlendFactor {
+        BlendFactor::from(self.0.dstAlphaBlendFactor())
+    }
 }
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StencilOp {
     Keep = 1,
     Zero = 2,
@@ -2559,6 +3291,22 @@ pub enum StencilOp {
     DecrWrap = 8,
 }
 
+impl From<DkStencilOp> for StencilOp {
+    fn from(value: DkStencilOp) -> Self {
+        match value {
+            DkStencilOp_DkStencilOp_Keep => StencilOp::Keep,
+            DkStencilOp_DkStencilOp_Zero => StencilOp::Zero,
+            DkStencilOp_DkStencilOp_Replace => StencilOp::Replace,
+            DkStencilOp_DkStencilOp_Incr => StencilOp::Incr,
+            DkStencilOp_DkStencilOp_Decr => StencilOp::Decr,
+            DkStencilOp_DkStencilOp_Invert => StencilOp::Invert,
+            DkStencilOp_DkStencilOp_IncrWrap => StencilOp::IncrWrap,
+            DkStencilOp_DkStencilOp_DecrWrap => StencilOp::DecrWrap,
+            _ => unreachable!("Invalid DkStencilOp value"),
+        }
+    }
+}
+
 #[repr(C)]
 pub struct DepthStencilState(DkDepthStencilState);
 
@@ -2653,6 +3401,54 @@ impl DepthStencilState {
 
         self
     }
+
+    pub fn get_depth_test_enable(&self) -> bool {
+        self.0.depthTestEnable() != 0
+    }
+
+    pub fn get_depth_write_enable(&self) -> bool {
+        self.0.depthWriteEnable() != 0
+    }
+
+    pub fn get_stencil_test_enable(&self) -> bool {
+        self.0.stencilTestEnable() != 0
+    }
+
+    pub fn get_depth_compare_op(&self) -> CompareOp {
+        CompareOp::from(self.0.depthCompareOp())
+    }
+
+    pub fn get_stencil_front_fail_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilFrontFailOp())
+    }
+
+    pub fn get_stencil_front_pass_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilFrontPassOp())
+    }
+
+    pub fn get_stencil_front_depth_fail_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilFrontDepthFailOp())
+    }
+
+    pub fn get_stencil_front_compare_op(&self) -> CompareOp {
+        CompareOp::from(self.0.stencilFrontCompareOp())
+    }
+
+    pub fn get_stencil_back_fail_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilBackFailOp())
+    }
+
+    pub fn get_stencil_back_pass_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilBackPassOp())
+    }
+
+    pub fn get_stencil_back_depth_fail_op(&self) -> StencilOp {
+        StencilOp::from(self.0.stencilBackDepthFailOp())
+    }
+
+    pub fn get_stencil_back_compare_op(&self) -> CompareOp {
+        CompareOp::from(self.0.stencilBackCompareOp())
+    }
 }
 
 #[repr(u32)]
@@ -2668,6 +3464,7 @@ pub enum TiledCacheOp {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VtxAttribSize {
     _1x32 = DkVtxAttribSize_DkVtxAttribSize_1x32,
     _2x32 = DkVtxAttribSize_DkVtxAttribSize_2x32,
@@ -2709,6 +3506,7 @@ impl From<DkVtxAttribSize> for VtxAttribSize {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VtxAttribType {
     None = DkVtxAttribType_DkVtxAttribType_None,
     Snorm = DkVtxAttribType_DkVtxAttribType_Snorm,
@@ -2880,27 +3678,182 @@ impl From<DkIdxFormat> for IdxFormat {
     }
 }
 
+pub trait Index: Copy {
+    const FORMAT: IdxFormat;
+    const MAX: u32;
+}
+
+impl Index for u8 {
+    const FORMAT: IdxFormat = IdxFormat::Uint8;
+    const MAX: u32 = u8::MAX as u32;
+}
+
+impl Index for u16 {
+    const FORMAT: IdxFormat = IdxFormat::Uint16;
+    const MAX: u32 = u16::MAX as u32;
+}
+
+impl Index for u32 {
+    const FORMAT: IdxFormat = IdxFormat::Uint32;
+    const MAX: u32 = u32::MAX;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IndexBuffer<T: Index> {
+    addr: GpuAddr,
+    len: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Index> IndexBuffer<T> {
+    pub fn new(addr: GpuAddr, len: u32) -> Self {
+        IndexBuffer { addr, len, _marker: PhantomData }
+    }
+
+    pub fn from_mem_block(mem: &MemBlock, offset: u32, len: u32) -> Self {
+        let size = len as u64 * std::mem::size_of::<T>() as u64;
+        assert!(offset as u64 + size <= mem.get_size() as u64, "index buffer exceeds the memory block");
+
+        Self::new(mem.get_gpu_addr() + offset as u64, len)
+    }
+
+    pub fn get_gpu_addr(&self) -> GpuAddr {
+        self.addr
+    }
+
+    pub fn get_format(&self) -> IdxFormat {
+        T::FORMAT
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.len * std::mem::size_of::<T>() as u32
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+pub trait IndirectArgs: Copy {}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+    pub first_instance: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DispatchIndirectArgs {
+    pub num_groups_x: u32,
+    pub num_groups_y: u32,
+    pub num_groups_z: u32,
+}
+
+impl IndirectArgs for DrawIndirectArgs {}
+impl IndirectArgs for DrawIndexedIndirectArgs {}
+impl IndirectArgs for DispatchIndirectArgs {}
+
+const _: () = assert!(std::mem::size_of::<DrawIndirectArgs>() == std::mem::size_of::<DkDrawIndirectData>());
+const _: () =
+    assert!(std::mem::size_of::<DrawIndexedIndirectArgs>() == std::mem::size_of::<DkDrawIndexedIndirectData>());
+const _: () = assert!(std::mem::size_of::<DispatchIndirectArgs>() == std::mem::size_of::<DkDispatchIndirectData>());
+
+pub const INDIRECT_ARGS_ALIGNMENT: u32 = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IndirectBuffer<T: IndirectArgs> {
+    addr: GpuAddr,
+    len: u32,
+    stride: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: IndirectArgs> IndirectBuffer<T> {
+    pub fn new(addr: GpuAddr, len: u32) -> Self {
+        Self::with_stride(addr, len, std::mem::size_of::<T>() as u32)
+    }
+
+    pub fn with_stride(addr: GpuAddr, len: u32, stride: u32) -> Self {
+        assert_eq!(addr % INDIRECT_ARGS_ALIGNMENT as u64, 0, "misaligned indirect buffer address");
+        assert_eq!(stride % INDIRECT_ARGS_ALIGNMENT, 0, "misaligned indirect buffer stride");
+        assert!(stride >= std::mem::size_of::<T>() as u32, "indirect buffer stride is smaller than its elements");
+
+        IndirectBuffer { addr, len, stride, _marker: PhantomData }
+    }
+
+    pub fn from_mem_block(mem: &MemBlock, offset: u32, len: u32) -> Self {
+        let size = len as u64 * std::mem::size_of::<T>() as u64;
+        assert!(offset as u64 + size <= mem.get_size() as u64, "indirect buffer exceeds the memory block");
+
+        Self::new(mem.get_gpu_addr() + offset as u64, len)
+    }
+
+    pub fn get_gpu_addr(&self) -> GpuAddr {
+        self.addr
+    }
+
+    pub fn get_entry_addr(&self, index: u32) -> GpuAddr {
+        assert!(index < self.len, "indirect buffer index {} is out of bounds for length {}", index, self.len);
+
+        self.addr + index as u64 * self.stride as u64
+    }
+
+    pub fn get_stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
 pub type ImageRect = DkImageRect;
 pub type CopyBuf = DkCopyBuf;
 
+// `Image` is more than a pointer to its `DkImage`, so the maker keeps its own array of image pointers.
 #[repr(C)]
-pub struct SwapchainMaker(DkSwapchainMaker);
+pub struct SwapchainMaker<'a>(DkSwapchainMaker, Vec<*const DkImage>, PhantomData<&'a Image>);
+
+impl<'a> SwapchainMaker<'a> {
+    pub fn new(device: &Device, native_window: *mut std::ffi::c_void, images: &'a [Image]) -> Self {
+        let image_ptrs: Vec<*const DkImage> =
+            images.iter().map(|image| image.0.as_ref().get_ref() as *const _).collect();
 
-impl SwapchainMaker {
-    pub fn new(device: &Device, native_window: *mut std::ffi::c_void, images: &[Image]) -> Self {
         unsafe {
             let mut maker: DkSwapchainMaker = std::mem::zeroed();
 
             maker.device = device.0;
             maker.nativeWindow = native_window;
-            maker.pImages = images.as_ptr() as *const _;
-            maker.numImages = images.len() as u32;
+            maker.pImages = image_ptrs.as_ptr() as *const _;
+            maker.numImages = image_ptrs.len() as u32;
 
-            SwapchainMaker(maker)
+            SwapchainMaker(maker, image_ptrs, PhantomData)
         }
     }
 
     pub fn create(&self) -> Swapchain {
-        unsafe { Swapchain(dkSwapchainCreate(&self.0)) }
+        unsafe { Swapchain(dkSwapchainCreate(&self.0), LastUse::default()) }
     }
 }
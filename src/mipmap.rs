@@ -0,0 +1,71 @@
+use deko3d_sys::*;
+
+use crate::{Barrier, CmdBuf, Filter, Image, ImageRect, ImageType, ImageView, InvalidateFlags};
+
+fn mip_extent(size: u32, level: u32) -> u32 {
+    (size >> level).max(1)
+}
+
+impl CmdBuf<'_> {
+    /// Fills mip levels 1.. of `image` by repeatedly blitting each level into the next.
+    ///
+    /// The chain is derived from the layout `image` was initialized with. Every array layer and cube
+    /// face is downsampled separately through single-layer, single-level views, with a barrier between
+    /// levels so each blit reads a finished source. The caller is still responsible for the barrier
+    /// between the last blit and sampling the image.
+    ///
+    /// Panics for 3D, multisampled, rectangle and buffer images, and for images created from raw
+    /// handles, whose layout is unknown.
+    pub fn generate_mipmaps(&self, image: &Image, filter: Filter) {
+        let layout = image.get_layout();
+        let (width, height, depth) = layout.get_dimensions();
+        let (view_type, height, num_layers) = match layout.get_type() {
+            ImageType::D1 => (ImageType::None, 1, 1),
+            ImageType::D2 => (ImageType::None, height, 1),
+            ImageType::D1Array => (ImageType::D1, 1, height),
+            ImageType::D2Array => (ImageType::D2, height, depth),
+            ImageType::Cubemap => (ImageType::D2, height, 6),
+            // The depth of a cubemap array counts individual faces, not cubes.
+            ImageType::CubemapArray => (ImageType::D2, height, depth),
+            ty => panic!("cannot generate mipmaps for {:?} images", ty),
+        };
+
+        let flags = match filter {
+            Filter::Nearest => DkBlitFlag_FilterNearest,
+            Filter::Linear => DkBlitFlag_FilterLinear,
+        };
+
+        for level in 1..layout.get_mip_levels() {
+            let src_rect = ImageRect {
+                x: 0,
+                y: 0,
+                z: 0,
+                width: mip_extent(width, level - 1),
+                height: mip_extent(height, level - 1),
+                depth: 1,
+            };
+            let dst_rect = ImageRect {
+                x: 0,
+                y: 0,
+                z: 0,
+                width: mip_extent(width, level),
+                height: mip_extent(height, level),
+                depth: 1,
+            };
+
+            for layer in 0..num_layers {
+                let mut src_view = ImageView::new(image);
+                src_view.set_type(view_type).set_layers(layer, 1).set_mip_levels(level - 1, 1);
+
+                let mut dst_view = ImageView::new(image);
+                dst_view.set_type(view_type).set_layers(layer, 1).set_mip_levels(level, 1);
+
+                self.blit_image(&src_view, &src_rect, &dst_view, &dst_rect, flags, 0);
+            }
+
+            if level + 1 < layout.get_mip_levels() {
+                self.barrier(Barrier::Full, InvalidateFlags::Image);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{CmdBuf, CmdBufMaker, CmdList, CmdMemPool, Device, Queue};
+
+/// Records command lists on several threads and stitches them into one primary list.
+///
+/// Every worker owns a `CmdBuf` backed by a shared [`CmdMemPool`], so memory used by a frame is
+/// only reused after [`ParallelRecorder::end_frame`]'s fence has signaled.
+pub struct ParallelRecorder<'a> {
+    primary: CmdBuf<'a>,
+    workers: Vec<CmdBuf<'a>>,
+    pool: CmdMemPool<'a>,
+}
+
+impl<'a> ParallelRecorder<'a> {
+    pub fn new(device: &'a Device, num_workers: usize, chunk_size: u32) -> Self {
+        assert!(num_workers > 0);
+
+        let pool = CmdMemPool::new(device, chunk_size);
+        let mut maker = CmdBufMaker::new(device);
+        maker.with_memory_pool(&pool);
+
+        ParallelRecorder { primary: maker.create(), workers: (0..num_workers).map(|_| maker.create()).collect(), pool }
+    }
+
+    pub fn get_worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn get_memory_pool(&self) -> &CmdMemPool<'a> {
+        &self.pool
+    }
+
+    /// Runs `job(index, cmd_buf)` for every index in `0..num_jobs` across the worker threads and
+    /// returns a primary list that calls the resulting lists in index order.
+    ///
+    /// Jobs are handed out round-robin, so job `i` always runs on worker `i % worker_count`.
+    pub fn record<F>(&mut self, num_jobs: usize, job: F) -> CmdList<'_>
+    where
+        F: Fn(usize, &CmdBuf) + Sync,
+    {
+        self.primary.clear();
+        for worker in self.workers.iter_mut() {
+            worker.clear();
+        }
+
+        let num_workers = self.workers.len();
+        let job = &job;
+        let mut lists = vec![0; num_jobs];
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .workers
+                .iter_mut()
+                .enumerate()
+                .map(|(first, worker)| {
+                    scope.spawn(move || {
+                        let worker = &*worker;
+                        (first..num_jobs)
+                            .step_by(num_workers)
+                            .map(|index| (index, worker.record(|cmd_buf| job(index, cmd_buf)).into_raw()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                for (index, list) in handle.join().expect("command recording job panicked") {
+                    lists[index] = list;
+                }
+            }
+        });
+
+        for list in lists {
+            self.primary.call_list(unsafe { CmdList::from_raw(list) });
+        }
+
+        self.primary.finish_list()
+    }
+
+    /// Marks the end of the frame on `queue`; command memory is recycled once it completes.
+    pub fn end_frame(&mut self, queue: &Queue) {
+        self.pool.end_frame(queue, std::iter::once(&mut self.primary).chain(self.workers.iter_mut()));
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::Device;
+
+/// The part of a device-backed pool shared by all of its clones.
+///
+/// The device lives outside the mutex so that pools stay covariant in `'d`.
+pub(crate) struct SharedPool<'d, S> {
+    device: &'d Device,
+    state: Mutex<S>,
+}
+
+impl<'d, S> SharedPool<'d, S> {
+    pub(crate) fn new(device: &'d Device, state: S) -> Self {
+        SharedPool { device, state: Mutex::new(state) }
+    }
+
+    pub(crate) fn get_device(&self) -> &'d Device {
+        self.device
+    }
+
+    /// Locks the state, even after a panic while it was held; pool state stays consistent between calls.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, S> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,215 @@
+use deko3d_sys::*;
+
+use crate::{
+    align_up, Device, Image, ImageFlags, ImageFormat, ImageLayout, ImageLayoutMaker, MemBlock, MemBlockFlags,
+    MemBlockMaker, Queue, Swapchain, SwapchainMaker,
+};
+
+pub struct PresenterMaker<'a> {
+    device: &'a Device,
+    native_window: *mut std::ffi::c_void,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    num_buffers: u32,
+    depth_format: Option<ImageFormat>,
+}
+
+impl<'a> PresenterMaker<'a> {
+    pub fn new(device: &'a Device, native_window: *mut std::ffi::c_void, width: u32, height: u32) -> Self {
+        PresenterMaker {
+            device,
+            native_window,
+            width,
+            height,
+            format: ImageFormat::RGBA8Unorm,
+            num_buffers: 2,
+            depth_format: None,
+        }
+    }
+
+    pub fn set_format(&mut self, format: ImageFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    pub fn get_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn set_num_buffers(&mut self, num_buffers: u32) -> &mut Self {
+        self.num_buffers = num_buffers;
+        self
+    }
+
+    pub fn get_num_buffers(&self) -> u32 {
+        self.num_buffers
+    }
+
+    /// Sets the format of a depth buffer shared by all framebuffers, or `None` for no depth buffer.
+    pub fn set_depth_format(&mut self, depth_format: Option<ImageFormat>) -> &mut Self {
+        self.depth_format = depth_format;
+        self
+    }
+
+    pub fn get_depth_format(&self) -> Option<ImageFormat> {
+        self.depth_format
+    }
+
+    pub fn create(&self) -> Presenter<'a> {
+        assert!(self.num_buffers >= 2, "presentation needs at least two framebuffers");
+
+        let mut presenter = Presenter {
+            swapchain: None,
+            framebuffers: Vec::new(),
+            depth_buffer: None,
+            memory: None,
+            device: self.device,
+            native_window: self.native_window,
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            num_buffers: self.num_buffers,
+            depth_format: self.depth_format,
+        };
+        presenter.build();
+        presenter
+    }
+}
+
+/// Owns the framebuffers, optional depth buffer and swapchain of a window.
+///
+/// Framebuffers are created with `UsagePresent | UsageRender | HwCompression` and share one memory
+/// block with the depth buffer. [`Presenter::resize`] tears all of it down and rebuilds it at the new
+/// resolution, e.g. when switching between handheld and docked mode.
+pub struct Presenter<'d> {
+    // The swapchain must be destroyed before the images it presents and their memory.
+    swapchain: Option<Swapchain>,
+    framebuffers: Vec<Image>,
+    depth_buffer: Option<Image>,
+    memory: Option<MemBlock>,
+    device: &'d Device,
+    native_window: *mut std::ffi::c_void,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    num_buffers: u32,
+    depth_format: Option<ImageFormat>,
+}
+
+impl Presenter<'_> {
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn get_depth_format(&self) -> Option<ImageFormat> {
+        self.depth_format
+    }
+
+    pub fn get_num_buffers(&self) -> u32 {
+        self.num_buffers
+    }
+
+    pub fn get_swapchain(&self) -> &Swapchain {
+        self.swapchain.as_ref().unwrap()
+    }
+
+    pub fn get_framebuffer(&self, image_slot: i32) -> &Image {
+        &self.framebuffers[image_slot as usize]
+    }
+
+    pub fn get_framebuffers(&self) -> &[Image] {
+        &self.framebuffers
+    }
+
+    pub fn get_depth_buffer(&self) -> Option<&Image> {
+        self.depth_buffer.as_ref()
+    }
+
+    pub fn acquire_image(&self, queue: &Queue) -> i32 {
+        queue.acquire_image(self.get_swapchain())
+    }
+
+    pub fn present_image(&self, queue: &Queue, image_slot: i32) {
+        queue.present_image(self.get_swapchain(), image_slot)
+    }
+
+    /// Rebuilds the framebuffers and swapchain at a new resolution.
+    ///
+    /// Waits for `queue` to go idle first, since the old framebuffers may still be rendered to or
+    /// presented. Does nothing if the resolution is unchanged.
+    pub fn resize(&mut self, queue: &Queue, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        queue.wait_idle();
+        self.destroy();
+
+        self.width = width;
+        self.height = height;
+        self.build();
+    }
+
+    fn make_layout(&self, format: ImageFormat, flags: ImageFlags) -> ImageLayout {
+        let mut layout = ImageLayout::new();
+        ImageLayoutMaker::new(self.device)
+            .set_flags(flags)
+            .set_format(format)
+            .set_dimensions(self.width, self.height, 1)
+            .initialize(&mut layout);
+        layout
+    }
+
+    fn build(&mut self) {
+        let fb_layout = self
+            .make_layout(self.format, ImageFlags::UsagePresent | ImageFlags::UsageRender | ImageFlags::HwCompression);
+        let depth_layout = self
+            .depth_format
+            .map(|format| self.make_layout(format, ImageFlags::UsageRender | ImageFlags::HwCompression));
+
+        let fb_size = align_up(fb_layout.get_size() as u32, fb_layout.get_alignment());
+        let depth_offset = fb_size * self.num_buffers;
+        let depth_size = depth_layout.as_ref().map_or(0, |layout| layout.get_size() as u32);
+        let depth_align = depth_layout.as_ref().map_or(1, |layout| layout.get_alignment());
+
+        let memory = MemBlockMaker::new(
+            self.device,
+            align_up(align_up(depth_offset, depth_align) + depth_size, DK_MEMBLOCK_ALIGNMENT),
+        )
+        .set_flags(MemBlockFlags::GpuCached | MemBlockFlags::Image)
+        .create();
+
+        self.framebuffers = (0..self.num_buffers)
+            .map(|i| {
+                let mut image = Image::new();
+                image.initialize(&fb_layout, &memory, i * fb_size);
+                image
+            })
+            .collect();
+
+        self.depth_buffer = depth_layout.map(|layout| {
+            let mut image = Image::new();
+            image.initialize(&layout, &memory, align_up(depth_offset, depth_align));
+            image
+        });
+
+        self.swapchain = Some(SwapchainMaker::new(self.device, self.native_window, &self.framebuffers).create());
+        self.memory = Some(memory);
+    }
+
+    fn destroy(&mut self) {
+        self.swapchain = None;
+        self.framebuffers.clear();
+        self.depth_buffer = None;
+        self.memory = None;
+    }
+}
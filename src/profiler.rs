@@ -0,0 +1,234 @@
+use std::collections::{HashMap, VecDeque};
+
+use deko3d_sys::*;
+
+use crate::{align_up, timestamp_to_ns, CmdBuf, Counter, Device, MemBlock, MemBlockMaker};
+
+// Each counter report is a 64-bit value followed by the 64-bit GPU timestamp it was taken at.
+pub(crate) const REPORT_SIZE: u32 = 16;
+
+pub(crate) fn read_report_timestamp(mem: &MemBlock, offset: u32) -> u64 {
+    unsafe { std::ptr::read_volatile((mem.get_cpu_addr() as *const u8).add(offset as usize + 8) as *const u64) }
+}
+
+const DEFAULT_HISTORY_LEN: usize = 60;
+
+/// The GPU time spent in a profiled scope during one frame, with the scopes nested inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeTiming {
+    pub name: &'static str,
+    pub duration_ns: u64,
+    pub children: Vec<ScopeTiming>,
+}
+
+/// Rolling statistics of a scope over the last frames it was recorded in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScopeStats {
+    pub min_ns: u64,
+    pub avg_ns: u64,
+    pub max_ns: u64,
+    pub num_samples: usize,
+}
+
+struct ScopeRecord {
+    name: &'static str,
+    parent: Option<usize>,
+}
+
+struct FrameQueries {
+    mem: MemBlock,
+    scopes: Vec<ScopeRecord>,
+    pending: bool,
+}
+
+/// Measures GPU time of named, nested scopes recorded into command buffers.
+///
+/// Each frame in flight has its own query memory block holding a begin and end timestamp per scope.
+/// A frame's timestamps are resolved when its slot comes around again in [`GpuProfiler::begin_frame`],
+/// into a tree of [`ScopeTiming`]s and per-path rolling statistics, where a scope's path is its name
+/// prefixed by those of its parents, separated by `/`. Scopes sharing a path within a frame are summed.
+pub struct GpuProfiler {
+    frames: Vec<FrameQueries>,
+    current: usize,
+    max_scopes: u32,
+    stack: Vec<Option<usize>>,
+    last_frame: Vec<ScopeTiming>,
+    history: HashMap<String, VecDeque<u64>>,
+    history_len: usize,
+}
+
+impl GpuProfiler {
+    /// Allocates a query block per frame slot with room for `max_scopes_per_frame` scopes. A slot is
+    /// resolved when it is reused `frames_in_flight` frames later, so the count must cover every frame
+    /// the GPU may still be executing.
+    pub fn new(device: &Device, frames_in_flight: u32, max_scopes_per_frame: u32) -> Self {
+        assert!(frames_in_flight > 0);
+
+        let size = align_up(max_scopes_per_frame * 2 * REPORT_SIZE, DK_MEMBLOCK_ALIGNMENT);
+        let frames = (0..frames_in_flight)
+            .map(|_| FrameQueries {
+                mem: MemBlockMaker::new(device, size).create(),
+                scopes: Vec::new(),
+                pending: false,
+            })
+            .collect();
+
+        GpuProfiler {
+            frames,
+            current: 0,
+            max_scopes: max_scopes_per_frame,
+            stack: Vec::new(),
+            last_frame: Vec::new(),
+            history: HashMap::new(),
+            history_len: DEFAULT_HISTORY_LEN,
+        }
+    }
+
+    /// Sets over how many frames the rolling statistics are computed.
+    pub fn set_history_len(&mut self, history_len: usize) -> &mut Self {
+        self.history_len = history_len.max(1);
+        for samples in self.history.values_mut() {
+            while samples.len() > self.history_len {
+                samples.pop_front();
+            }
+        }
+        self
+    }
+
+    pub fn get_history_len(&self) -> usize {
+        self.history_len
+    }
+
+    /// Turns the timestamps the previous frame in this slot recorded into [`GpuProfiler::get_last_frame`]
+    /// and the rolling statistics, then clears the slot for new scopes.
+    ///
+    /// Reading the timestamps requires that frame's command lists to have finished executing, which
+    /// holds after waiting on its fence.
+    pub fn begin_frame(&mut self) {
+        debug_assert!(self.stack.is_empty(), "previous frame has unterminated scopes");
+
+        if self.frames[self.current].pending {
+            self.resolve(self.current);
+        }
+
+        let frame = &mut self.frames[self.current];
+        frame.scopes.clear();
+        frame.pending = false;
+    }
+
+    pub fn end_frame(&mut self) {
+        debug_assert!(self.stack.is_empty(), "end_frame called with open scopes");
+
+        self.frames[self.current].pending = true;
+        self.current = (self.current + 1) % self.frames.len();
+    }
+
+    /// Opens a scope nested inside the currently open one. Scopes past the per-frame limit are dropped.
+    pub fn begin_scope(&mut self, cmd_buf: &CmdBuf, name: &'static str) {
+        let frame = &mut self.frames[self.current];
+        if frame.scopes.len() as u32 >= self.max_scopes {
+            self.stack.push(None);
+            return;
+        }
+
+        let index = frame.scopes.len();
+        let parent = self.stack.iter().rev().flatten().next().copied();
+        frame.scopes.push(ScopeRecord { name, parent });
+        self.stack.push(Some(index));
+
+        let addr = frame.mem.get_gpu_addr() + (index as u32 * 2 * REPORT_SIZE) as DkGpuAddr;
+        cmd_buf.report_counter(Counter::Timestamp, addr);
+    }
+
+    pub fn end_scope(&mut self, cmd_buf: &CmdBuf) {
+        let index = match self.stack.pop() {
+            Some(Some(index)) => index,
+            Some(None) => return,
+            None => {
+                debug_assert!(false, "end_scope without a matching begin_scope");
+                return;
+            }
+        };
+
+        let frame = &self.frames[self.current];
+        let addr = frame.mem.get_gpu_addr() + ((index as u32 * 2 + 1) * REPORT_SIZE) as DkGpuAddr;
+        cmd_buf.report_counter(Counter::Timestamp, addr);
+    }
+
+    /// Records `f` inside a scope. `f` gets the profiler back to open nested scopes.
+    pub fn scope<R, F: FnOnce(&mut Self) -> R>(&mut self, cmd_buf: &CmdBuf, name: &'static str, f: F) -> R {
+        self.begin_scope(cmd_buf, name);
+        let result = f(self);
+        self.end_scope(cmd_buf);
+        result
+    }
+
+    /// Returns the scope tree of the most recently resolved frame.
+    pub fn get_last_frame(&self) -> &[ScopeTiming] {
+        &self.last_frame
+    }
+
+    pub fn get_stats(&self, path: &str) -> Option<ScopeStats> {
+        self.history.get(path).and_then(Self::make_stats)
+    }
+
+    pub fn get_all_stats(&self) -> impl Iterator<Item = (&str, ScopeStats)> + '_ {
+        self.history.iter().filter_map(|(path, samples)| Some((path.as_str(), Self::make_stats(samples)?)))
+    }
+
+    fn make_stats(samples: &VecDeque<u64>) -> Option<ScopeStats> {
+        Some(ScopeStats {
+            min_ns: *samples.iter().min()?,
+            avg_ns: samples.iter().sum::<u64>() / samples.len() as u64,
+            max_ns: *samples.iter().max()?,
+            num_samples: samples.len(),
+        })
+    }
+
+    fn resolve(&mut self, slot: usize) {
+        let frame = &self.frames[slot];
+        let durations: Vec<u64> = (0..frame.scopes.len() as u32)
+            .map(|index| {
+                let begin = read_report_timestamp(&frame.mem, index * 2 * REPORT_SIZE);
+                let end = read_report_timestamp(&frame.mem, (index * 2 + 1) * REPORT_SIZE);
+                timestamp_to_ns(end.wrapping_sub(begin))
+            })
+            .collect();
+
+        let mut samples = HashMap::new();
+        self.last_frame = Self::build_tree(&frame.scopes, &durations, None, "", &mut samples);
+
+        for (path, duration_ns) in samples {
+            let history = self.history.entry(path).or_default();
+            if history.len() == self.history_len {
+                history.pop_front();
+            }
+            history.push_back(duration_ns);
+        }
+    }
+
+    fn build_tree(
+        scopes: &[ScopeRecord],
+        durations: &[u64],
+        parent: Option<usize>,
+        parent_path: &str,
+        samples: &mut HashMap<String, u64>,
+    ) -> Vec<ScopeTiming> {
+        scopes
+            .iter()
+            .enumerate()
+            .filter(|(_, scope)| scope.parent == parent)
+            .map(|(index, scope)| {
+                let path = if parent_path.is_empty() {
+                    scope.name.to_string()
+                } else {
+                    format!("{}/{}", parent_path, scope.name)
+                };
+                let children = Self::build_tree(scopes, durations, Some(index), &path, samples);
+                *samples.entry(path).or_default() += durations[index];
+
+                ScopeTiming { name: scope.name, duration_ns: durations[index], children }
+            })
+            .collect()
+    }
+}
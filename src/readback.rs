@@ -0,0 +1,82 @@
+use deko3d_sys::*;
+
+use crate::{
+    align_up, Barrier, CmdBufMaker, CopyBuf, DekoError, Device, Fence, Image, ImageFormat, ImageRect, ImageView,
+    InvalidateFlags, MemBlockFlags, MemBlockMaker, Queue, Result,
+};
+
+pub use deko3d_image::{DecodeError, HostFormat, HostImage, HostPixels};
+
+const READBACK_CMD_MEM_SIZE: u32 = DK_MEMBLOCK_ALIGNMENT;
+
+// Maps formats to the host decoder's, treating sRGB as its linear and depth as its color counterpart.
+fn host_format(format: ImageFormat) -> Option<HostFormat> {
+    Some(match format {
+        ImageFormat::R8Unorm => HostFormat::R8Unorm,
+        ImageFormat::RG8Unorm => HostFormat::RG8Unorm,
+        ImageFormat::RGBA8Unorm | ImageFormat::RGBA8UnormSrgb => HostFormat::RGBA8Unorm,
+        ImageFormat::RGBX8Unorm | ImageFormat::RGBX8UnormSrgb => HostFormat::RGBX8Unorm,
+        ImageFormat::BGRA8Unorm | ImageFormat::BGRA8UnormSrgb => HostFormat::BGRA8Unorm,
+        ImageFormat::BGRX8Unorm | ImageFormat::BGRX8UnormSrgb => HostFormat::BGRX8Unorm,
+        ImageFormat::R16Float => HostFormat::R16Float,
+        ImageFormat::RG16Float => HostFormat::RG16Float,
+        ImageFormat::RGBA16Float => HostFormat::RGBA16Float,
+        ImageFormat::RGBX16Float => HostFormat::RGBX16Float,
+        ImageFormat::R16Unorm | ImageFormat::Z16 => HostFormat::R16Unorm,
+        ImageFormat::RGBA16Unorm => HostFormat::RGBA16Unorm,
+        ImageFormat::RGBX16Unorm => HostFormat::RGBX16Unorm,
+        ImageFormat::R32Float | ImageFormat::ZF32 => HostFormat::R32Float,
+        ImageFormat::RG32Float => HostFormat::RG32Float,
+        ImageFormat::RGBA32Float => HostFormat::RGBA32Float,
+        ImageFormat::RGBX32Float => HostFormat::RGBX32Float,
+        ImageFormat::RGB10A2Unorm => HostFormat::RGB10A2Unorm,
+        ImageFormat::RG11B10Float => HostFormat::RG11B10Float,
+        _ => return None,
+    })
+}
+
+impl Image {
+    /// Copies `rect` of `view` into host memory and decodes it, blocking until the GPU is done.
+    ///
+    /// The staging memory is allocated from `device` for the duration of the call; it is passed in
+    /// explicitly because neither images nor queues keep track of the device they belong to.
+    ///
+    /// Returns `DekoError::BadInput` if the format of `view` is unknown, e.g. for images created from raw
+    /// handles, or if `rect` is too large to stage, and `DekoError::NotImplemented` for formats without a host conversion (compressed,
+    /// integer and stencil formats).
+    pub fn read_back(&self, device: &Device, queue: &Queue, view: &ImageView, rect: &ImageRect) -> Result<HostImage> {
+        debug_assert!(std::ptr::eq(view.get_image(), self), "view is not of this image");
+        debug_assert_eq!(rect.depth, 1, "only a single layer can be read back");
+
+        let format = host_format(view.get_format().ok_or(DekoError::BadInput)?).ok_or(DekoError::NotImplemented)?;
+        let bpp = format.bytes_per_pixel();
+        let row_size = rect.width.checked_mul(bpp).ok_or(DekoError::BadInput)?;
+        let size = row_size
+            .checked_mul(rect.height)
+            .filter(|&size| size <= u32::MAX - (DK_MEMBLOCK_ALIGNMENT - 1))
+            .ok_or(DekoError::BadInput)?;
+
+        let staging = MemBlockMaker::new(device, align_up(size, DK_MEMBLOCK_ALIGNMENT))
+            .set_flags(MemBlockFlags::CpuCached | MemBlockFlags::GpuCached)
+            .create();
+        let cmd_mem = MemBlockMaker::new(device, READBACK_CMD_MEM_SIZE).create();
+
+        let mut cmd_buf = CmdBufMaker::new(device).create();
+        cmd_buf.add_memory(&cmd_mem, 0, READBACK_CMD_MEM_SIZE);
+
+        let dst = CopyBuf { addr: staging.get_gpu_addr(), rowLength: row_size, imageHeight: rect.height };
+        let cmds = cmd_buf.record(|cmd_buf| {
+            cmd_buf.barrier(Barrier::Full, InvalidateFlags::None);
+            cmd_buf.copy_image_to_buffer(view, rect, &dst, 0);
+        });
+
+        let mut fence = Fence::new();
+        queue.submit_commands(cmds);
+        queue.signal_fence(&mut fence, true);
+        fence.wait_no_timeout()?;
+
+        staging.flush_cpu_cache(0, size)?;
+        let data = unsafe { std::slice::from_raw_parts(staging.get_cpu_addr() as *const u8, size as usize) };
+        HostImage::decode(format, rect.width, rect.height, data).map_err(|_| DekoError::BadInput)
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use deko3d_sys::*;
+
+use crate::{CmdList, DekoError, Fence, Queue, QueueFlags, QueueMaker, Result};
+
+const DEFAULT_HISTORY_LEN: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubmissionRecord {
+    pub list: DkCmdList,
+    pub submitted_at: Instant,
+}
+
+/// A fence signaled through a [`RecoverableQueue`] that had not completed yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FenceRecord {
+    /// Position of the fence among those signaled in its queue generation.
+    pub index: u64,
+    pub signaled_at: Instant,
+}
+
+/// Snapshot of a queue's state taken when it was found in the error state.
+#[derive(Debug, Clone)]
+pub struct QueueDiagnostics {
+    pub generation: u32,
+    pub flags: QueueFlags,
+    pub command_memory_size: u32,
+    pub flush_threshold: u32,
+    pub recent_submissions: Vec<SubmissionRecord>,
+    pub pending_fences: Vec<FenceRecord>,
+    pub detected_at: Instant,
+}
+
+impl fmt::Display for QueueDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "queue generation {} entered the error state", self.generation)?;
+        writeln!(f, "  flags: {:?}", self.flags)?;
+        writeln!(
+            f,
+            "  command memory: {:#x} bytes, flush threshold {:#x}",
+            self.command_memory_size, self.flush_threshold
+        )?;
+        writeln!(f, "  {} pending fences:", self.pending_fences.len())?;
+        for record in self.pending_fences.iter() {
+            writeln!(
+                f,
+                "    fence #{}, signaled {:?} before detection",
+                record.index,
+                self.detected_at.saturating_duration_since(record.signaled_at)
+            )?;
+        }
+        writeln!(f, "  last {} submissions:", self.recent_submissions.len())?;
+
+        for record in self.recent_submissions.iter().rev() {
+            writeln!(
+                f,
+                "    list {:#x}, {:?} before detection",
+                record.list,
+                self.detected_at.saturating_duration_since(record.submitted_at)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+type RecoveryCallback = Box<dyn FnMut(&QueueDiagnostics, &Queue)>;
+
+/// A queue that notices GPU faults and replaces itself with a fresh queue.
+///
+/// Only the queue itself is recreated. Everything tied to the dead queue stays behind: fences
+/// still pending at the time of the fault never signal, and command lists submitted to it never
+/// execute. The recovery callback receives the new queue and is responsible for rebuilding such
+/// objects, i.e. replacing fences it waits on, recording command lists again and re-uploading
+/// transient resources.
+pub struct RecoverableQueue {
+    queue: Queue,
+    maker: QueueMaker,
+    generation: u32,
+    history: VecDeque<SubmissionRecord>,
+    history_len: usize,
+    pending_fences: Vec<(Fence, FenceRecord)>,
+    fence_count: u64,
+    callback: Option<RecoveryCallback>,
+}
+
+impl RecoverableQueue {
+    pub fn new(maker: &QueueMaker) -> Self {
+        RecoverableQueue {
+            queue: maker.create(),
+            maker: QueueMaker(maker.0, maker.1.clone()),
+            generation: 0,
+            history: VecDeque::with_capacity(DEFAULT_HISTORY_LEN),
+            history_len: DEFAULT_HISTORY_LEN,
+            pending_fences: Vec::new(),
+            fence_count: 0,
+            callback: None,
+        }
+    }
+
+    pub fn get_queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Number of times the queue was recreated.
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn set_history_len(&mut self, len: usize) -> &mut Self {
+        self.history_len = len;
+        while self.history.len() > len {
+            self.history.pop_front();
+        }
+        self
+    }
+
+    /// Sets the callback run after the queue was recreated; see [`RecoverableQueue`] for what it
+    /// has to rebuild.
+    pub fn set_recovery_callback<F: FnMut(&QueueDiagnostics, &Queue) + 'static>(&mut self, callback: F) -> &mut Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn submit_commands(&mut self, cmds: CmdList) -> Result {
+        if self.history_len != 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(SubmissionRecord { list: cmds.0, submitted_at: Instant::now() });
+        }
+
+        self.queue.submit_commands(cmds);
+        self.check()
+    }
+
+    pub fn signal_fence(&mut self, fence: &mut Fence, flush: bool) -> Result {
+        self.queue.signal_fence(fence, flush);
+
+        self.retire_fences();
+        let record = FenceRecord { index: self.fence_count, signaled_at: Instant::now() };
+        self.pending_fences.push((unsafe { Fence::from_raw(fence.0) }, record));
+        self.fence_count += 1;
+
+        self.check()
+    }
+
+    /// Waits on a fence signaled by this queue; a timeout is treated as a possible hang and
+    /// followed by an error state check.
+    pub fn wait_fence_timeout(&mut self, fence: &mut Fence, timeout: Duration) -> Result {
+        let result = fence.wait_timeout(timeout);
+        if result.is_ok() {
+            self.retire_fences();
+        }
+
+        self.check()?;
+        result
+    }
+
+    pub fn flush(&mut self) -> Result {
+        self.queue.flush();
+        self.check()
+    }
+
+    pub fn wait_idle(&mut self) -> Result {
+        self.check()?;
+        self.queue.wait_idle();
+        self.retire_fences();
+        self.check()
+    }
+
+    /// Recovers the queue if it is in the error state, returning `DekoError::BadState` when a
+    /// recovery happened.
+    pub fn check(&mut self) -> Result {
+        if !self.queue.is_in_error_state() {
+            return Ok(());
+        }
+
+        self.retire_fences();
+        let diagnostics = self.get_diagnostics();
+        self.recover();
+
+        if let Some(callback) = self.callback.as_mut() {
+            callback(&diagnostics, &self.queue);
+        }
+
+        Err(DekoError::BadState)
+    }
+
+    pub fn get_diagnostics(&self) -> QueueDiagnostics {
+        QueueDiagnostics {
+            generation: self.generation,
+            flags: self.maker.get_flags(),
+            command_memory_size: self.maker.get_command_memory_size(),
+            flush_threshold: self.maker.get_flush_threshold(),
+            recent_submissions: self.history.iter().copied().collect(),
+            pending_fences: self.pending_fences.iter().map(|(_, record)| *record).collect(),
+            detected_at: Instant::now(),
+        }
+    }
+
+    fn recover(&mut self) {
+        self.queue = self.maker.create();
+
+        self.generation += 1;
+        self.history.clear();
+        self.pending_fences.clear();
+        self.fence_count = 0;
+    }
+
+    fn retire_fences(&mut self) {
+        self.pending_fences.retain_mut(|(fence, _)| fence.wait(0) == Err(DekoError::Timeout));
+    }
+}
@@ -0,0 +1,693 @@
+use deko3d_sys::*;
+
+use crate::{
+    align_up, Barrier, CmdBuf, Device, GpuAddr, Image, ImageFlags, ImageFormat, ImageLayout, ImageLayoutMaker,
+    InvalidateFlags, MemBlock, MemBlockFlags, MemBlockMaker, MsMode,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GraphResource(usize);
+
+/// Engine a pass runs on, which decides how long later passes have to wait for it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassKind {
+    Graphics,
+    Compute,
+    Transfer,
+}
+
+impl PassKind {
+    fn barrier(self) -> Barrier {
+        match self {
+            PassKind::Graphics => Barrier::Fragments,
+            PassKind::Compute | PassKind::Transfer => Barrier::Full,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Access {
+    ColorAttachment,
+    DepthAttachment,
+    Sampled,
+    Storage,
+    Uniform,
+    VertexInput,
+    Indirect,
+    Transfer,
+}
+
+impl Access {
+    fn is_attachment(self) -> bool {
+        matches!(self, Access::ColorAttachment | Access::DepthAttachment)
+    }
+
+    fn invalidate_flags(self, resource: &ResourceNode) -> InvalidateFlags {
+        match (self, resource) {
+            // Buffers are read through the shader cache, which also holds uniforms.
+            (Access::Uniform, _) | (Access::Storage, ResourceNode::Buffer(..)) => InvalidateFlags::Shader,
+            (Access::Sampled | Access::Storage, _) => InvalidateFlags::Image,
+            _ => InvalidateFlags::None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransientImageDesc {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub ms_mode: MsMode,
+    pub flags: ImageFlags,
+}
+
+impl TransientImageDesc {
+    pub fn new(format: ImageFormat, width: u32, height: u32) -> Self {
+        TransientImageDesc {
+            format,
+            width,
+            height,
+            ms_mode: MsMode::Ms1x,
+            flags: ImageFlags::UsageRender | ImageFlags::HwCompression,
+        }
+    }
+}
+
+enum ResourceNode<'a> {
+    Image(&'a Image),
+    Buffer(GpuAddr, u32),
+    Transient(TransientImageDesc),
+}
+
+#[derive(Copy, Clone)]
+struct ResourceAccess {
+    resource: GraphResource,
+    access: Access,
+    read: bool,
+    write: bool,
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&CmdBuf, &PassContext) + 'a>;
+
+struct PassNode<'a> {
+    name: &'static str,
+    kind: PassKind,
+    accesses: Vec<ResourceAccess>,
+    side_effects: bool,
+    execute: Option<PassFn<'a>>,
+}
+
+pub struct PassBuilder<'g, 'a> {
+    pass: &'g mut PassNode<'a>,
+}
+
+impl<'g, 'a> PassBuilder<'g, 'a> {
+    /// Reads the current contents of `resource`.
+    pub fn read(&mut self, resource: GraphResource, access: Access) -> &mut Self {
+        self.pass.accesses.push(ResourceAccess { resource, access, read: true, write: false });
+        self
+    }
+
+    /// Overwrites `resource` without looking at its previous contents.
+    pub fn write(&mut self, resource: GraphResource, access: Access) -> &mut Self {
+        self.pass.accesses.push(ResourceAccess { resource, access, read: false, write: true });
+        self
+    }
+
+    /// Updates `resource` in place, e.g. blending into or loading an attachment.
+    pub fn modify(&mut self, resource: GraphResource, access: Access) -> &mut Self {
+        self.pass.accesses.push(ResourceAccess { resource, access, read: true, write: true });
+        self
+    }
+
+    /// Keeps the pass even if nothing reads what it writes.
+    pub fn set_side_effects(&mut self, side_effects: bool) -> &mut Self {
+        self.pass.side_effects = side_effects;
+        self
+    }
+
+    pub fn execute<F: FnOnce(&CmdBuf, &PassContext) + 'a>(&mut self, f: F) -> &mut Self {
+        self.pass.execute = Some(Box::new(f));
+        self
+    }
+}
+
+pub struct PassContext<'r> {
+    images: Vec<Option<&'r Image>>,
+    buffers: Vec<Option<(GpuAddr, u32)>>,
+}
+
+impl<'r> PassContext<'r> {
+    pub fn get_image(&self, resource: GraphResource) -> &'r Image {
+        self.images[resource.0].expect("resource is not an image")
+    }
+
+    pub fn get_buffer(&self, resource: GraphResource) -> (GpuAddr, u32) {
+        self.buffers[resource.0].expect("resource is not a buffer")
+    }
+}
+
+/// Memory backing the transient images of a graph execution, which must outlive the GPU work that uses them.
+pub struct RenderGraphResources {
+    memory: Option<MemBlock>,
+    culled_passes: Vec<&'static str>,
+}
+
+impl RenderGraphResources {
+    pub fn get_memory_size(&self) -> u32 {
+        self.memory.as_ref().map_or(0, |memory| memory.get_size())
+    }
+
+    pub fn get_culled_passes(&self) -> &[&'static str] {
+        &self.culled_passes
+    }
+}
+
+#[derive(Clone)]
+struct HazardState {
+    // Kind of the last pass that wrote the resource, until a barrier waited for it.
+    pending_write: Option<PassKind>,
+    last_write_access: Option<Access>,
+    // Kind of the passes that read the resource since it was last synchronized.
+    pending_read: Option<PassKind>,
+    invalidated: InvalidateFlags,
+}
+
+impl Default for HazardState {
+    fn default() -> Self {
+        HazardState {
+            pending_write: None,
+            last_write_access: None,
+            pending_read: None,
+            invalidated: InvalidateFlags::None,
+        }
+    }
+}
+
+// First and last live pass using a resource.
+type Lifetime = Option<(usize, usize)>;
+
+// Offset and size of a transient image in the aliased memory block.
+type Placement = Option<(u32, u32)>;
+
+fn barrier_rank(barrier: Barrier) -> u32 {
+    match barrier {
+        Barrier::None => 0,
+        Barrier::Tiles => 1,
+        Barrier::Fragments => 2,
+        Barrier::Primitives => 3,
+        Barrier::Full => 4,
+    }
+}
+
+fn stronger(a: Barrier, b: Barrier) -> Barrier {
+    if barrier_rank(a) >= barrier_rank(b) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Inserts barriers between producers and consumers, aliases transient images and culls passes whose
+/// results are never used.
+///
+/// Passes are not reordered: they run in the order they were added, and a read sees the contents left
+/// by the passes added before it. Callers therefore add producers before their consumers; reading a
+/// transient image before any earlier pass wrote it is caught by a debug assertion.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<ResourceNode<'a>>,
+    outputs: Vec<GraphResource>,
+    passes: Vec<PassNode<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn import_image(&mut self, image: &'a Image) -> GraphResource {
+        self.add_resource(ResourceNode::Image(image))
+    }
+
+    pub fn import_buffer(&mut self, addr: GpuAddr, size: u32) -> GraphResource {
+        self.add_resource(ResourceNode::Buffer(addr, size))
+    }
+
+    pub fn create_transient_image(&mut self, desc: TransientImageDesc) -> GraphResource {
+        self.add_resource(ResourceNode::Transient(desc))
+    }
+
+    /// Marks a resource as needed after the graph has run, keeping the passes that produce it.
+    pub fn mark_output(&mut self, resource: GraphResource) {
+        self.outputs.push(resource);
+    }
+
+    pub fn add_pass(&mut self, name: &'static str, kind: PassKind) -> PassBuilder<'_, 'a> {
+        self.passes.push(PassNode { name, kind, accesses: Vec::new(), side_effects: false, execute: None });
+        PassBuilder { pass: self.passes.last_mut().unwrap() }
+    }
+
+    /// Records every live pass into `cmd_buf`.
+    pub fn execute(self, device: &Device, cmd_buf: &CmdBuf) -> RenderGraphResources {
+        let live = self.cull();
+        let lifetimes = self.get_lifetimes(&live);
+        let (placements, memory) = self.allocate(device, &lifetimes);
+
+        let mut transients = Vec::new();
+        let mut transient_index = vec![None; self.resources.len()];
+        for (id, resource) in self.resources.iter().enumerate() {
+            if let (ResourceNode::Transient(desc), Some((offset, _))) = (resource, placements[id]) {
+                let layout = make_layout(device, desc);
+                let mut image = Image::new();
+                image.initialize(&layout, memory.as_ref().unwrap(), offset);
+
+                transient_index[id] = Some(transients.len());
+                transients.push(image);
+            }
+        }
+
+        let context = PassContext {
+            images: self
+                .resources
+                .iter()
+                .enumerate()
+                .map(|(id, resource)| match resource {
+                    ResourceNode::Image(image) => Some(*image),
+                    ResourceNode::Transient(_) => transient_index[id].map(|index| &transients[index]),
+                    ResourceNode::Buffer(..) => None,
+                })
+                .collect(),
+            buffers: self
+                .resources
+                .iter()
+                .map(|resource| match resource {
+                    ResourceNode::Buffer(addr, size) => Some((*addr, *size)),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        let aliases = self.find_aliases(&lifetimes, &placements);
+        let barriers = self.plan_barriers(&live, &lifetimes, &aliases);
+        let mut culled_passes = Vec::new();
+
+        for ((pass, live), (barrier, invalidate)) in self.passes.into_iter().zip(live).zip(barriers) {
+            if !live {
+                culled_passes.push(pass.name);
+                continue;
+            }
+
+            if barrier != Barrier::None || !invalidate.is_empty() {
+                cmd_buf.barrier(barrier, invalidate);
+            }
+
+            if let Some(execute) = pass.execute {
+                execute(cmd_buf, &context);
+            }
+        }
+
+        RenderGraphResources { memory, culled_passes }
+    }
+
+    fn add_resource(&mut self, resource: ResourceNode<'a>) -> GraphResource {
+        self.resources.push(resource);
+        GraphResource(self.resources.len() - 1)
+    }
+
+    // The barrier to record before every pass, or none for culled passes.
+    fn plan_barriers(
+        &self,
+        live: &[bool],
+        lifetimes: &[Lifetime],
+        aliases: &[Option<usize>],
+    ) -> Vec<(Barrier, InvalidateFlags)> {
+        let mut states = vec![HazardState::default(); self.resources.len()];
+        let mut barriers = vec![(Barrier::None, InvalidateFlags::None); self.passes.len()];
+
+        for (index, pass) in self.passes.iter().enumerate().filter(|(index, _)| live[*index]) {
+            for access in pass.accesses.iter() {
+                if lifetimes[access.resource.0].map(|(first, _)| first) != Some(index) {
+                    continue;
+                }
+
+                debug_assert!(
+                    !(access.read && matches!(self.resources[access.resource.0], ResourceNode::Transient(_))),
+                    "pass `{}` reads a transient image before any earlier pass writes it",
+                    pass.name
+                );
+
+                // A transient placed over an earlier one inherits its hazards on first use.
+                if let Some(previous) = aliases[access.resource.0] {
+                    states[access.resource.0] = states[previous].clone();
+                }
+            }
+
+            let (barrier, invalidate) = required_barrier(&self.resources, &states, pass);
+            if barrier != Barrier::None || !invalidate.is_empty() {
+                barriers[index] = (barrier, invalidate);
+
+                for state in states.iter_mut() {
+                    if state.pending_write.is_some_and(|kind| barrier_rank(kind.barrier()) <= barrier_rank(barrier)) {
+                        state.pending_write = None;
+                    }
+                    if state.pending_read.is_some_and(|kind| barrier_rank(kind.barrier()) <= barrier_rank(barrier)) {
+                        state.pending_read = None;
+                    }
+                    state.invalidated |= invalidate;
+                }
+            }
+
+            for access in pass.accesses.iter() {
+                let state = &mut states[access.resource.0];
+                if access.write {
+                    state.pending_write = Some(pass.kind);
+                    state.last_write_access = Some(access.access);
+                    state.pending_read = None;
+                    state.invalidated = InvalidateFlags::None;
+                } else if access.read {
+                    state.pending_read = Some(match state.pending_read {
+                        Some(kind) if barrier_rank(kind.barrier()) > barrier_rank(pass.kind.barrier()) => kind,
+                        _ => pass.kind,
+                    });
+                }
+            }
+        }
+
+        barriers
+    }
+
+    fn cull(&self) -> Vec<bool> {
+        let mut needed = vec![false; self.resources.len()];
+        for output in self.outputs.iter() {
+            needed[output.0] = true;
+        }
+
+        let mut live = vec![false; self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let writes_needed = pass.accesses.iter().any(|access| access.write && needed[access.resource.0]);
+            if !pass.side_effects && !writes_needed {
+                continue;
+            }
+
+            live[index] = true;
+            for access in pass.accesses.iter() {
+                // Anything written without being read is replaced here, so earlier producers of
+                // it are only needed by passes that run before this one.
+                if access.write && !access.read {
+                    needed[access.resource.0] = false;
+                }
+            }
+            for access in pass.accesses.iter().filter(|access| access.read) {
+                needed[access.resource.0] = true;
+            }
+        }
+
+        live
+    }
+
+    fn get_lifetimes(&self, live: &[bool]) -> Vec<Lifetime> {
+        let mut lifetimes: Vec<Lifetime> = vec![None; self.resources.len()];
+        for (index, pass) in self.passes.iter().enumerate().filter(|(index, _)| live[*index]) {
+            for access in pass.accesses.iter() {
+                let lifetime = &mut lifetimes[access.resource.0];
+                *lifetime = Some(lifetime.map_or((index, index), |(first, _)| (first, index)));
+            }
+        }
+
+        lifetimes
+    }
+
+    fn allocate(&self, device: &Device, lifetimes: &[Lifetime]) -> (Vec<Placement>, Option<MemBlock>) {
+        let requests: Vec<(usize, u32, u32)> = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(|(id, resource)| match resource {
+                ResourceNode::Transient(desc) if lifetimes[id].is_some() => {
+                    let layout = make_layout(device, desc);
+                    Some((id, layout.get_size() as u32, layout.get_alignment()))
+                }
+                _ => None,
+            })
+            .collect();
+        let (placements, total) = place_transients(self.resources.len(), lifetimes, requests);
+
+        let memory = (total != 0).then(|| {
+            MemBlockMaker::new(device, align_up(total, DK_MEMBLOCK_ALIGNMENT))
+                .set_flags(MemBlockFlags::GpuCached | MemBlockFlags::Image)
+                .create()
+        });
+
+        (placements, memory)
+    }
+
+    // For every transient, the most recent transient that used overlapping memory before it.
+    fn find_aliases(&self, lifetimes: &[Lifetime], placements: &[Placement]) -> Vec<Option<usize>> {
+        (0..self.resources.len())
+            .map(|id| {
+                let (offset, size) = placements[id]?;
+                let (first, _) = lifetimes[id]?;
+
+                (0..self.resources.len())
+                    .filter(|&other| other != id)
+                    .filter(|&other| placements[other].is_some_and(|(o, s)| o < offset + size && offset < o + s))
+                    .filter_map(|other| lifetimes[other].map(|(_, last)| (last, other)))
+                    .filter(|&(last, _)| last < first)
+                    .max()
+                    .map(|(_, other)| other)
+            })
+            .collect()
+    }
+}
+
+// Places each `(resource, size, alignment)` request at the lowest offset free during its lifetime,
+// returning the placements and the memory size they need.
+fn place_transients(
+    num_resources: usize,
+    lifetimes: &[Lifetime],
+    mut requests: Vec<(usize, u32, u32)>,
+) -> (Vec<Placement>, u32) {
+    // Biggest first so small images fill the gaps.
+    requests.sort_by_key(|&(id, size, _)| (std::cmp::Reverse(size), id));
+
+    let mut placements = vec![None; num_resources];
+    let mut placed: Vec<(u32, u32, (usize, usize))> = Vec::new();
+    let mut total = 0;
+
+    for (id, size, alignment) in requests {
+        let lifetime = lifetimes[id].unwrap();
+        let mut conflicts: Vec<(u32, u32)> = placed
+            .iter()
+            .filter(|(_, _, other)| lifetime.0 <= other.1 && other.0 <= lifetime.1)
+            .map(|&(offset, size, _)| (offset, offset + size))
+            .collect();
+        conflicts.sort_unstable();
+
+        let mut offset = 0;
+        for (start, end) in conflicts {
+            if align_up(offset, alignment) + size <= start {
+                break;
+            }
+            offset = offset.max(end);
+        }
+        let offset = align_up(offset, alignment);
+
+        placements[id] = Some((offset, size));
+        placed.push((offset, size, lifetime));
+        total = total.max(offset + size);
+    }
+
+    (placements, total)
+}
+
+fn required_barrier(resources: &[ResourceNode], states: &[HazardState], pass: &PassNode) -> (Barrier, InvalidateFlags) {
+    let mut barrier = Barrier::None;
+    let mut invalidate = InvalidateFlags::None;
+
+    for access in pass.accesses.iter() {
+        let state = &states[access.resource.0];
+
+        if let Some(kind) = state.pending_write {
+            // The rasterizer keeps attachment writes in order on its own.
+            let ordered = access.access.is_attachment()
+                && state.last_write_access == Some(access.access)
+                && kind == PassKind::Graphics
+                && pass.kind == PassKind::Graphics;
+            if !ordered {
+                barrier = stronger(barrier, kind.barrier());
+            }
+        }
+
+        if access.write {
+            if let Some(kind) = state.pending_read {
+                barrier = stronger(barrier, kind.barrier());
+            }
+        }
+
+        if access.read && state.last_write_access.is_some() {
+            invalidate |= access.access.invalidate_flags(&resources[access.resource.0]) - state.invalidated;
+        }
+    }
+
+    (barrier, invalidate)
+}
+
+fn make_layout(device: &Device, desc: &TransientImageDesc) -> ImageLayout {
+    let mut layout = ImageLayout::new();
+    ImageLayoutMaker::new(device)
+        .set_flags(desc.flags)
+        .set_format(desc.format)
+        .set_ms_mode(desc.ms_mode)
+        .set_dimensions(desc.width, desc.height, 1)
+        .initialize(&mut layout);
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transient(graph: &mut RenderGraph) -> GraphResource {
+        graph.create_transient_image(TransientImageDesc::new(ImageFormat::RGBA8Unorm, 64, 64))
+    }
+
+    fn plan(graph: &RenderGraph, aliases: &[Option<usize>]) -> Vec<(Barrier, InvalidateFlags)> {
+        let live = graph.cull();
+        let lifetimes = graph.get_lifetimes(&live);
+        graph.plan_barriers(&live, &lifetimes, aliases)
+    }
+
+    #[test]
+    fn cull_keeps_producers_of_outputs() {
+        let mut graph = RenderGraph::new();
+        let (a, b, c) = (graph.import_buffer(0, 16), graph.import_buffer(16, 16), graph.import_buffer(32, 16));
+        graph.add_pass("a", PassKind::Compute).write(a, Access::Storage);
+        graph.add_pass("unused", PassKind::Compute).write(b, Access::Storage);
+        graph.add_pass("c", PassKind::Compute).read(a, Access::Storage).write(c, Access::Storage);
+        graph.mark_output(c);
+
+        assert_eq!(graph.cull(), [true, false, true]);
+    }
+
+    #[test]
+    fn cull_drops_overwritten_producers() {
+        let mut graph = RenderGraph::new();
+        let a = graph.import_buffer(0, 16);
+        graph.add_pass("overwritten", PassKind::Compute).write(a, Access::Storage);
+        graph.add_pass("modified", PassKind::Compute).write(a, Access::Storage);
+        graph.add_pass("last", PassKind::Compute).modify(a, Access::Storage);
+        graph.mark_output(a);
+
+        assert_eq!(graph.cull(), [false, true, true]);
+    }
+
+    #[test]
+    fn cull_keeps_side_effects() {
+        let mut graph = RenderGraph::new();
+        let a = graph.import_buffer(0, 16);
+        graph.add_pass("producer", PassKind::Compute).write(a, Access::Storage);
+        graph.add_pass("readback", PassKind::Transfer).read(a, Access::Transfer).set_side_effects(true);
+
+        assert_eq!(graph.cull(), [true, true]);
+    }
+
+    #[test]
+    fn sampling_an_attachment_waits_for_fragments() {
+        let mut graph = RenderGraph::new();
+        let (color, target) = (transient(&mut graph), transient(&mut graph));
+        graph.add_pass("scene", PassKind::Graphics).write(color, Access::ColorAttachment);
+        graph.add_pass("post", PassKind::Graphics).read(color, Access::Sampled).write(target, Access::ColorAttachment);
+        graph.mark_output(target);
+
+        assert_eq!(
+            plan(&graph, &[None, None]),
+            [(Barrier::None, InvalidateFlags::None), (Barrier::Fragments, InvalidateFlags::Image)]
+        );
+    }
+
+    #[test]
+    fn attachment_writes_in_order_need_no_barrier() {
+        let mut graph = RenderGraph::new();
+        let color = transient(&mut graph);
+        graph.add_pass("opaque", PassKind::Graphics).write(color, Access::ColorAttachment);
+        graph.add_pass("transparent", PassKind::Graphics).modify(color, Access::ColorAttachment);
+        graph.mark_output(color);
+
+        assert_eq!(plan(&graph, &[None]), [(Barrier::None, InvalidateFlags::None); 2]);
+    }
+
+    #[test]
+    fn compute_results_are_invalidated_once() {
+        let mut graph = RenderGraph::new();
+        let buffer = graph.import_buffer(0, 256);
+        graph.add_pass("simulate", PassKind::Compute).write(buffer, Access::Storage);
+        graph.add_pass("draw", PassKind::Graphics).read(buffer, Access::Uniform).set_side_effects(true);
+        graph.add_pass("draw again", PassKind::Graphics).read(buffer, Access::Uniform).set_side_effects(true);
+
+        assert_eq!(
+            plan(&graph, &[None]),
+            [
+                (Barrier::None, InvalidateFlags::None),
+                (Barrier::Full, InvalidateFlags::Shader),
+                (Barrier::None, InvalidateFlags::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn aliased_transient_waits_for_previous_readers() {
+        let mut graph = RenderGraph::new();
+        let (first, output, second) = (transient(&mut graph), graph.import_buffer(0, 16), transient(&mut graph));
+        graph.add_pass("render", PassKind::Graphics).write(first, Access::ColorAttachment);
+        graph.add_pass("reduce", PassKind::Compute).read(first, Access::Sampled).write(output, Access::Storage);
+        graph.add_pass("reuse", PassKind::Compute).write(second, Access::Storage).set_side_effects(true);
+        graph.mark_output(output);
+
+        assert_eq!(plan(&graph, &[None, None, None])[2], (Barrier::None, InvalidateFlags::None));
+        assert_eq!(plan(&graph, &[None, None, Some(first.0)])[2], (Barrier::Full, InvalidateFlags::None));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "before any earlier pass writes it")]
+    fn reading_a_transient_before_writing_it_panics() {
+        let mut graph = RenderGraph::new();
+        let color = transient(&mut graph);
+        graph.add_pass("consumer", PassKind::Graphics).read(color, Access::Sampled).set_side_effects(true);
+        graph.add_pass("producer", PassKind::Graphics).write(color, Access::ColorAttachment);
+
+        plan(&graph, &[None]);
+    }
+
+    #[test]
+    fn transients_with_disjoint_lifetimes_share_memory() {
+        let lifetimes = [Some((0, 1)), Some((2, 3)), Some((1, 2))];
+        let (placements, total) = place_transients(3, &lifetimes, vec![(0, 256, 256), (1, 256, 256), (2, 128, 256)]);
+
+        assert_eq!(placements, [Some((0, 256)), Some((0, 256)), Some((256, 128))]);
+        assert_eq!(total, 384);
+    }
+
+    #[test]
+    fn placement_respects_alignment() {
+        let lifetimes = [Some((0, 1)), Some((0, 1))];
+        let (placements, total) = place_transients(2, &lifetimes, vec![(0, 100, 64), (1, 100, 256)]);
+
+        assert_eq!(placements, [Some((0, 100)), Some((256, 100))]);
+        assert_eq!(total, 356);
+    }
+
+    #[test]
+    fn aliases_follow_the_latest_earlier_user() {
+        let mut graph = RenderGraph::new();
+        graph.import_buffer(0, 16);
+        for _ in 0..3 {
+            transient(&mut graph);
+        }
+
+        let lifetimes = [Some((0, 2)), Some((0, 0)), Some((1, 1)), Some((2, 2))];
+        let placements = [None, Some((0, 256)), Some((0, 256)), Some((128, 256))];
+        assert_eq!(graph.find_aliases(&lifetimes, &placements), [None, None, Some(1), Some(2)]);
+    }
+}
@@ -0,0 +1,210 @@
+use deko3d_sys::*;
+
+use crate::{ClearColor, CmdBuf, ColorMask, ImageView, Scissor, TiledCacheOp, Viewport};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearValue {
+    Color(ClearColor),
+    DepthStencil(f32, u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoadOp {
+    Load,
+    Clear(ClearValue),
+    DontCare,
+}
+
+#[derive(Copy, Clone)]
+pub enum StoreOp<'a> {
+    Store,
+    Discard,
+    /// Resolves the multisampled attachment into the given view, then discards it.
+    Resolve(&'a ImageView<'a>),
+}
+
+#[derive(Copy, Clone)]
+pub struct Attachment<'a> {
+    pub view: &'a ImageView<'a>,
+    pub load_op: LoadOp,
+    pub store_op: StoreOp<'a>,
+}
+
+/// Describes the targets of a render pass and what happens to them at its start and end.
+///
+/// The render area is the largest one all attachments cover, unless a smaller one is set with
+/// [`RenderPassDesc::set_render_area`]; the viewport and scissor 0 are set to it when the pass begins.
+#[derive(Default)]
+pub struct RenderPassDesc<'a> {
+    color: Vec<Attachment<'a>>,
+    depth: Option<Attachment<'a>>,
+    tiled_cache: bool,
+    render_area: Option<(u32, u32)>,
+}
+
+impl<'a> RenderPassDesc<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_color_attachment(
+        &mut self,
+        view: &'a ImageView<'a>,
+        load_op: LoadOp,
+        store_op: StoreOp<'a>,
+    ) -> &mut Self {
+        assert!(self.color.len() < DK_MAX_RENDER_TARGETS as usize);
+        debug_assert!(!matches!(load_op, LoadOp::Clear(ClearValue::DepthStencil(..))));
+
+        self.color.push(Attachment { view, load_op, store_op });
+        self
+    }
+
+    pub fn set_depth_attachment(
+        &mut self,
+        view: &'a ImageView<'a>,
+        load_op: LoadOp,
+        store_op: StoreOp<'a>,
+    ) -> &mut Self {
+        debug_assert!(!matches!(load_op, LoadOp::Clear(ClearValue::Color(_))));
+
+        self.depth = Some(Attachment { view, load_op, store_op });
+        self
+    }
+
+    /// Enables the tiled cache for the duration of the pass and flushes it at the end.
+    pub fn set_tiled_cache(&mut self, enable: bool) -> &mut Self {
+        self.tiled_cache = enable;
+        self
+    }
+
+    /// Restricts rendering to the top-left `width` x `height` corner of the attachments, e.g. the scaled
+    /// size picked by a [`crate::DynamicResolution`]. `None` renders to the whole attachments.
+    pub fn set_render_area(&mut self, render_area: Option<(u32, u32)>) -> &mut Self {
+        self.render_area = render_area;
+        self
+    }
+
+    /// Returns the explicit render area, or else the attachments' dimensions.
+    pub fn get_render_area(&self) -> Option<(u32, u32)> {
+        self.render_area.or_else(|| self.get_dimensions())
+    }
+
+    pub fn get_color_attachments(&self) -> &[Attachment<'a>] {
+        &self.color
+    }
+
+    pub fn get_depth_attachment(&self) -> Option<&Attachment<'a>> {
+        self.depth.as_ref()
+    }
+
+    /// Returns the size all attachments cover, or `None` without attachments or if the dimensions of one
+    /// of them are unknown, as for images created from raw handles.
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        let mut dimensions =
+            self.color.iter().chain(self.depth.iter()).map(|attachment| attachment.view.get_dimensions());
+        let first = dimensions.next()??;
+
+        dimensions.try_fold(first, |(w0, h0), dimensions| dimensions.map(|(w1, h1)| (w0.min(w1), h0.min(h1))))
+    }
+}
+
+/// An active render pass; its store operations run when it is ended or dropped.
+pub struct RenderPass<'c, 'a> {
+    cmd_buf: &'c CmdBuf<'c>,
+    desc: &'c RenderPassDesc<'a>,
+}
+
+impl<'c, 'a> RenderPass<'c, 'a> {
+    pub fn get_cmd_buf(&self) -> &'c CmdBuf<'c> {
+        self.cmd_buf
+    }
+
+    pub fn end(self) {}
+}
+
+impl<'c, 'a> Drop for RenderPass<'c, 'a> {
+    fn drop(&mut self) {
+        let cmd_buf = self.cmd_buf;
+        let desc = self.desc;
+
+        for (id, attachment) in desc.color.iter().enumerate() {
+            match attachment.store_op {
+                StoreOp::Store => {}
+                StoreOp::Discard => cmd_buf.discard_color(id as u32),
+                StoreOp::Resolve(dst) => {
+                    cmd_buf.resolve_image(attachment.view, dst);
+                    cmd_buf.discard_color(id as u32);
+                }
+            }
+        }
+
+        if let Some(depth) = desc.depth.as_ref() {
+            match depth.store_op {
+                StoreOp::Store => {}
+                StoreOp::Discard => cmd_buf.discard_depth_stencil(),
+                StoreOp::Resolve(dst) => {
+                    cmd_buf.resolve_image(depth.view, dst);
+                    cmd_buf.discard_depth_stencil();
+                }
+            }
+        }
+
+        if desc.tiled_cache {
+            cmd_buf.tiled_cache_op(TiledCacheOp::Flush);
+            cmd_buf.tiled_cache_op(TiledCacheOp::Disable);
+        }
+    }
+}
+
+impl CmdBuf<'_> {
+    pub fn begin_render_pass<'c, 'a>(&'c self, desc: &'c RenderPassDesc<'a>) -> RenderPass<'c, 'a> {
+        let color_targets: Vec<&ImageView> = desc.color.iter().map(|attachment| attachment.view).collect();
+        self.bind_render_targets(&color_targets, desc.depth.as_ref().map(|attachment| attachment.view));
+
+        if desc.tiled_cache {
+            self.tiled_cache_op(TiledCacheOp::Enable);
+        }
+
+        match desc.get_render_area() {
+            Some((width, height)) => {
+                if cfg!(debug_assertions) && desc.render_area.is_some() {
+                    let fits = desc.get_dimensions().is_none_or(|(w, h)| width <= w && height <= h);
+                    assert!(fits, "render area exceeds the attachments");
+                }
+
+                self.set_viewports(
+                    0,
+                    &[Viewport { x: 0.0, y: 0.0, width: width as f32, height: height as f32, near: 0.0, far: 1.0 }],
+                );
+                self.set_scissors(0, &[Scissor { x: 0, y: 0, width, height }]);
+            }
+            None => debug_assert!(
+                desc.color.is_empty() && desc.depth.is_none(),
+                "attachment dimensions are unknown, e.g. for images created from raw handles; set a render area"
+            ),
+        }
+
+        for (id, attachment) in desc.color.iter().enumerate() {
+            match attachment.load_op {
+                LoadOp::Load => {}
+                LoadOp::Clear(ClearValue::Color(color)) => self.clear_color(id as u32, ColorMask::RGBA, color),
+                LoadOp::Clear(ClearValue::DepthStencil(..)) => unreachable!("depth clear on a color attachment"),
+                LoadOp::DontCare => self.discard_color(id as u32),
+            }
+        }
+
+        if let Some(depth) = desc.depth.as_ref() {
+            match depth.load_op {
+                LoadOp::Load => {}
+                LoadOp::Clear(ClearValue::DepthStencil(depth, stencil)) => {
+                    self.clear_depth_stencil(true, depth, 0xff, stencil)
+                }
+                LoadOp::Clear(ClearValue::Color(_)) => unreachable!("color clear on a depth attachment"),
+                LoadOp::DontCare => self.discard_depth_stencil(),
+            }
+        }
+
+        RenderPass { cmd_buf: self, desc }
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+
+use crate::{CmdList, Fence, Queue, Result, SubmissionToken};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct QueueId(usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubmissionId {
+    queue: QueueId,
+    serial: u64,
+}
+
+impl SubmissionId {
+    pub fn get_queue(&self) -> QueueId {
+        self.queue
+    }
+}
+
+struct ScheduledQueue<'a> {
+    queue: &'a Queue,
+    next_serial: u64,
+    // Every submission before this serial has been flushed by the scheduler.
+    flushed_serial: u64,
+    // Fences signaled after each submission that may still be pending, oldest first.
+    fences: VecDeque<(u64, Fence)>,
+    // Copy of the most recently retired fence, handed out for submissions that already completed.
+    last_retired: Option<Fence>,
+    // Highest serial of every other queue this queue has already waited for.
+    waited: Vec<u64>,
+}
+
+impl<'a> ScheduledQueue<'a> {
+    fn retire(&mut self) {
+        while let Some((_, fence)) = self.fences.front_mut() {
+            if fence.wait(0).is_err() {
+                break;
+            }
+            self.last_retired = self.fences.pop_front().map(|(_, fence)| fence);
+        }
+    }
+
+    fn find_fence(&self, serial: u64) -> Option<Fence> {
+        self.fences.iter().find(|(s, _)| *s == serial).map(|(_, fence)| Fence(fence.0, None))
+    }
+
+    fn find_token_fence(&self, serial: u64) -> Fence {
+        match self.find_fence(serial) {
+            Some(fence) => fence,
+            None => Fence(self.last_retired.as_ref().expect("submission was never made").0, None),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.flushed_serial != self.next_serial {
+            self.queue.flush();
+            self.flushed_serial = self.next_serial;
+        }
+    }
+}
+
+/// Submits command lists to several queues, turning declared dependencies between
+/// submissions into fence signals and waits.
+///
+/// Submissions to the same queue execute in order, so dependencies on them only need the
+/// barriers already recorded in the command lists.
+///
+/// Queues flush on their own once the command memory set by
+/// [`QueueMaker::set_flush_threshold`](crate::QueueMaker::set_flush_threshold) fills up; on top of
+/// that, the scheduler only flushes a submission when another queue waits on it or a token for it is
+/// requested.
+pub struct Scheduler<'a> {
+    queues: Vec<ScheduledQueue<'a>>,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new() -> Self {
+        Scheduler { queues: Vec::new() }
+    }
+
+    pub fn add_queue(&mut self, queue: &'a Queue) -> QueueId {
+        for q in self.queues.iter_mut() {
+            q.waited.push(0);
+        }
+
+        self.queues.push(ScheduledQueue {
+            queue,
+            next_serial: 1,
+            flushed_serial: 1,
+            fences: VecDeque::new(),
+            last_retired: None,
+            waited: vec![0; self.queues.len() + 1],
+        });
+
+        QueueId(self.queues.len() - 1)
+    }
+
+    pub fn get_queue(&self, id: QueueId) -> &'a Queue {
+        self.queues[id.0].queue
+    }
+
+    pub fn submit(&mut self, queue: QueueId, cmds: CmdList, dependencies: &[SubmissionId]) -> SubmissionId {
+        for dep in dependencies.iter().filter(|dep| dep.queue != queue) {
+            assert!(
+                dep.serial < self.queues[dep.queue.0].next_serial,
+                "dependency on a submission that was never made"
+            );
+
+            if self.queues[queue.0].waited[dep.queue.0] >= dep.serial {
+                continue;
+            }
+
+            let src = &mut self.queues[dep.queue.0];
+            src.retire();
+            let mut fence = match src.find_fence(dep.serial) {
+                Some(fence) => fence,
+                None => continue,
+            };
+
+            // The waiting queue would stall forever on a signal that never reaches the GPU.
+            if src.flushed_serial <= dep.serial {
+                src.flush();
+            }
+
+            let dst = &mut self.queues[queue.0];
+            dst.queue.wait_fence(&mut fence);
+            dst.waited[dep.queue.0] = dep.serial;
+        }
+
+        let q = &mut self.queues[queue.0];
+        q.retire();
+
+        let serial = q.next_serial;
+        let mut fence = Fence::new();
+
+        q.queue.submit_commands(cmds);
+        q.queue.signal_fence(&mut fence, false);
+        q.fences.push_back((serial, fence));
+        q.next_serial += 1;
+
+        SubmissionId { queue, serial }
+    }
+
+    /// Returns a token for `id`, flushing its queue so the token can actually complete.
+    pub fn get_token(&mut self, id: SubmissionId) -> SubmissionToken {
+        let q = &mut self.queues[id.queue.0];
+        if q.flushed_serial <= id.serial {
+            q.flush();
+        }
+
+        q.retire();
+        SubmissionToken(q.find_token_fence(id.serial))
+    }
+
+    pub fn is_complete(&mut self, id: SubmissionId) -> bool {
+        let q = &mut self.queues[id.queue.0];
+        q.retire();
+        !q.fences.iter().any(|(serial, _)| *serial == id.serial)
+    }
+
+    pub fn wait(&mut self, id: SubmissionId) -> Result {
+        self.get_token(id).wait()
+    }
+
+    pub fn flush(&mut self) {
+        for q in self.queues.iter_mut() {
+            q.flush();
+        }
+    }
+
+    pub fn wait_idle(&mut self) {
+        for q in self.queues.iter_mut() {
+            q.queue.wait_idle();
+            q.flushed_serial = q.next_serial;
+            if let Some((_, fence)) = q.fences.drain(..).next_back() {
+                q.last_retired = Some(fence);
+            }
+        }
+    }
+}
+
+impl<'a> Default for Scheduler<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,526 @@
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::*;
+
+macro_rules! impl_serde_via {
+    ($state:ty, $desc:ident) => {
+        impl Serialize for $state {
+            fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                $desc::from(self).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $state {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                $desc::deserialize(deserializer).map(<$state>::from)
+            }
+        }
+    };
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "RasterizerState")]
+struct RasterizerStateDesc {
+    rasterizer_enable: bool,
+    depth_clamp_enable: bool,
+    fill_rectangle_enable: bool,
+    polygon_mode_front: PolygonMode,
+    polygon_mode_back: PolygonMode,
+    cull_mode: Face,
+    front_face: FrontFace,
+    provoking_vertex: ProvokingVertex,
+    polygon_smooth_enable_mask: u32,
+    depth_bias_enable_mask: u32,
+}
+
+impl From<&RasterizerState> for RasterizerStateDesc {
+    fn from(state: &RasterizerState) -> Self {
+        RasterizerStateDesc {
+            rasterizer_enable: state.get_rasterizer_enable(),
+            depth_clamp_enable: state.get_depth_clamp_enable(),
+            fill_rectangle_enable: state.get_fill_rectangle_enable(),
+            polygon_mode_front: state.get_polygon_mode_front(),
+            polygon_mode_back: state.get_polygon_mode_back(),
+            cull_mode: state.get_cull_mode(),
+            front_face: state.get_front_face(),
+            provoking_vertex: state.get_provoking_vertex(),
+            polygon_smooth_enable_mask: state.get_polygon_smooth_enable_mask(),
+            depth_bias_enable_mask: state.get_depth_bias_enable_mask(),
+        }
+    }
+}
+
+impl From<RasterizerStateDesc> for RasterizerState {
+    fn from(desc: RasterizerStateDesc) -> Self {
+        let mut state = RasterizerState::new();
+        state
+            .set_rasterizer_enable(desc.rasterizer_enable)
+            .set_depth_clamp_enable(desc.depth_clamp_enable)
+            .set_fill_rectangle_enable(desc.fill_rectangle_enable)
+            .set_polygon_mode_front(desc.polygon_mode_front)
+            .set_polygon_mode_back(desc.polygon_mode_back)
+            .set_cull_mode(desc.cull_mode)
+            .set_front_face(desc.front_face)
+            .set_provoking_vertex(desc.provoking_vertex)
+            .set_polygon_smooth_enable_mask(desc.polygon_smooth_enable_mask)
+            .set_depth_bias_enable_mask(desc.depth_bias_enable_mask);
+        state
+    }
+}
+
+impl_serde_via!(RasterizerState, RasterizerStateDesc);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "MultisampleState")]
+struct MultisampleStateDesc {
+    mode: MsMode,
+    rasterizer_mode: MsMode,
+    alpha_to_coverage_enable: bool,
+    alpha_to_coverage_dither: bool,
+    coverage_to_color_enable: bool,
+    coverage_to_color_output: u32,
+    coverage_modulation: CoverageModulation,
+    sample_locations: [u32; 4],
+}
+
+impl From<&MultisampleState> for MultisampleStateDesc {
+    fn from(state: &MultisampleState) -> Self {
+        MultisampleStateDesc {
+            mode: state.get_mode(),
+            rasterizer_mode: state.get_rasterizer_mode(),
+            alpha_to_coverage_enable: state.get_alpha_to_coverage_enable(),
+            alpha_to_coverage_dither: state.get_alpha_to_coverage_dither(),
+            coverage_to_color_enable: state.get_coverage_to_color_enable(),
+            coverage_to_color_output: state.get_coverage_to_color_output(),
+            coverage_modulation: state.get_coverage_modulation(),
+            sample_locations: state.0.sampleLocations,
+        }
+    }
+}
+
+impl From<MultisampleStateDesc> for MultisampleState {
+    fn from(desc: MultisampleStateDesc) -> Self {
+        let mut state = MultisampleState::new();
+        state
+            .set_mode(desc.mode)
+            .set_rasterizer_mode(desc.rasterizer_mode)
+            .set_alpha_to_coverage_enable(desc.alpha_to_coverage_enable)
+            .set_alpha_to_coverage_dither(desc.alpha_to_coverage_dither)
+            .set_coverage_to_color_enable(desc.coverage_to_color_enable)
+            .set_coverage_to_color_output(desc.coverage_to_color_output)
+            .set_coverage_modulation(desc.coverage_modulation);
+        state.0.sampleLocations = desc.sample_locations;
+        state
+    }
+}
+
+impl_serde_via!(MultisampleState, MultisampleStateDesc);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ColorState")]
+struct ColorStateDesc {
+    blend_enable_mask: u8,
+    logic_op: LogicOp,
+    alpha_compare_op: CompareOp,
+}
+
+impl From<&ColorState> for ColorStateDesc {
+    fn from(state: &ColorState) -> Self {
+        ColorStateDesc {
+            blend_enable_mask: state.get_blend_enable_mask(),
+            logic_op: state.get_logic_op(),
+            alpha_compare_op: state.get_alpha_compare_op(),
+        }
+    }
+}
+
+impl From<ColorStateDesc> for ColorState {
+    fn from(desc: ColorStateDesc) -> Self {
+        let mut state = ColorState::new();
+        state
+            .set_blend_enable_mask(desc.blend_enable_mask)
+            .set_logic_op(desc.logic_op)
+            .set_alpha_compare_op(desc.alpha_compare_op);
+        state
+    }
+}
+
+impl_serde_via!(ColorState, ColorStateDesc);
+
+const COLOR_CHANNELS: [(char, ColorMask); 4] =
+    [('R', ColorMask::R), ('G', ColorMask::G), ('B', ColorMask::B), ('A', ColorMask::A)];
+
+// Masks are written as their channel names, e.g. "RGB", rather than as raw bits.
+impl Serialize for ColorMask {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let names: String =
+            COLOR_CHANNELS.iter().filter(|(_, channel)| self.contains(*channel)).map(|(name, _)| name).collect();
+        serializer.serialize_str(&names)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorMask {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.chars().try_fold(ColorMask::empty(), |mask, name| {
+            match COLOR_CHANNELS.iter().find(|(channel_name, _)| *channel_name == name) {
+                Some((_, channel)) => Ok(mask | *channel),
+                None => Err(D::Error::invalid_value(Unexpected::Char(name), &"one of the channels R, G, B and A")),
+            }
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ColorWriteState")]
+struct ColorWriteStateDesc {
+    masks: [ColorMask; DK_MAX_RENDER_TARGETS as usize],
+}
+
+impl From<&ColorWriteState> for ColorWriteStateDesc {
+    fn from(state: &ColorWriteState) -> Self {
+        let mut masks = [ColorMask::empty(); DK_MAX_RENDER_TARGETS as usize];
+        for (id, mask) in masks.iter_mut().enumerate() {
+            *mask = ColorMask::from_bits_truncate(state.get_mask(id as u32));
+        }
+
+        ColorWriteStateDesc { masks }
+    }
+}
+
+impl From<ColorWriteStateDesc> for ColorWriteState {
+    fn from(desc: ColorWriteStateDesc) -> Self {
+        let mut state = ColorWriteState::new();
+        for (id, mask) in desc.masks.iter().enumerate() {
+            state.set_mask(id as u32, mask.bits());
+        }
+        state
+    }
+}
+
+impl_serde_via!(ColorWriteState, ColorWriteStateDesc);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "BlendState")]
+struct BlendStateDesc {
+    color_blend_op: BlendOp,
+    src_color_blend_factor: BlendFactor,
+    dst_color_blend_factor: BlendFactor,
+    alpha_blend_op: BlendOp,
+    src_alpha_blend_factor: BlendFactor,
+    dst_alpha_blend_factor: BlendFactor,
+}
+
+impl From<&BlendState> for BlendStateDesc {
+    fn from(state: &BlendState) -> Self {
+        BlendStateDesc {
+            color_blend_op: state.get_color_blend_op(),
+            src_color_blend_factor: state.get_src_color_blend_factor(),
+            dst_color_blend_factor: state.get_dst_color_blend_factor(),
+            alpha_blend_op: state.get_alpha_blend_op(),
+            src_alpha_blend_factor: state.get_src_alpha_blend_factor(),
+            dst_alpha_blend_factor: state.get_dst_alpha_blend_factor(),
+        }
+    }
+}
+
+impl From<BlendStateDesc> for BlendState {
+    fn from(desc: BlendStateDesc) -> Self {
+        let mut state = BlendState::new();
+        state.set_ops(desc.color_blend_op, desc.alpha_blend_op).set_factors(
+            desc.src_color_blend_factor,
+            desc.dst_color_blend_factor,
+            desc.src_alpha_blend_factor,
+            desc.dst_alpha_blend_factor,
+        );
+        state
+    }
+}
+
+impl_serde_via!(BlendState, BlendStateDesc);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "DepthStencilState")]
+struct DepthStencilStateDesc {
+    depth_test_enable: bool,
+    depth_write_enable: bool,
+    stencil_test_enable: bool,
+    depth_compare_op: CompareOp,
+    stencil_front_fail_op: StencilOp,
+    stencil_front_pass_op: StencilOp,
+    stencil_front_depth_fail_op: StencilOp,
+    stencil_front_compare_op: CompareOp,
+    stencil_back_fail_op: StencilOp,
+    stencil_back_pass_op: StencilOp,
+    stencil_back_depth_fail_op: StencilOp,
+    stencil_back_compare_op: CompareOp,
+}
+
+impl From<&DepthStencilState> for DepthStencilStateDesc {
+    fn from(state: &DepthStencilState) -> Self {
+        DepthStencilStateDesc {
+            depth_test_enable: state.get_depth_test_enable(),
+            depth_write_enable: state.get_depth_write_enable(),
+            stencil_test_enable: state.get_stencil_test_enable(),
+            depth_compare_op: state.get_depth_compare_op(),
+            stencil_front_fail_op: state.get_stencil_front_fail_op(),
+            stencil_front_pass_op: state.get_stencil_front_pass_op(),
+            stencil_front_depth_fail_op: state.get_stencil_front_depth_fail_op(),
+            stencil_front_compare_op: state.get_stencil_front_compare_op(),
+            stencil_back_fail_op: state.get_stencil_back_fail_op(),
+            stencil_back_pass_op: state.get_stencil_back_pass_op(),
+            stencil_back_depth_fail_op: state.get_stencil_back_depth_fail_op(),
+            stencil_back_compare_op: state.get_stencil_back_compare_op(),
+        }
+    }
+}
+
+impl From<DepthStencilStateDesc> for DepthStencilState {
+    fn from(desc: DepthStencilStateDesc) -> Self {
+        let mut state = DepthStencilState::new();
+        state
+            .set_depth_test_enable(desc.depth_test_enable)
+            .set_depth_write_enable(desc.depth_write_enable)
+            .set_stencil_test_enable(desc.stencil_test_enable)
+            .set_depth_compare_op(desc.depth_compare_op)
+            .set_stencil_front_fail_op(desc.stencil_front_fail_op)
+            .set_stencil_front_pass_op(desc.stencil_front_pass_op)
+            .set_stencil_front_depth_fail_op(desc.stencil_front_depth_fail_op)
+            .set_stencil_front_compare_op(desc.stencil_front_compare_op)
+            .set_stencil_back_fail_op(desc.stencil_back_fail_op)
+            .set_stencil_back_pass_op(desc.stencil_back_pass_op)
+            .set_stencil_back_depth_fail_op(desc.stencil_back_depth_fail_op)
+            .set_stencil_back_compare_op(desc.stencil_back_compare_op);
+        state
+    }
+}
+
+impl_serde_via!(DepthStencilState, DepthStencilStateDesc);
+
+#[derive(Serialize, Deserialize)]
+enum BorderColor {
+    Float([f32; 4]),
+    Uint([u32; 4]),
+    Sint([i32; 4]),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Sampler")]
+struct SamplerDesc {
+    min_filter: Filter,
+    mag_filter: Filter,
+    mip_filter: MipFilter,
+    wrap_mode: [WrapMode; 3],
+    lod_clamp_min: f32,
+    lod_clamp_max: f32,
+    lod_bias: f32,
+    lod_snap: f32,
+    compare_enable: bool,
+    compare_op: CompareOp,
+    border_color: BorderColor,
+    max_anisotropy: f32,
+    reduction_mode: SamplerReduction,
+}
+
+impl From<&Sampler> for SamplerDesc {
+    fn from(sampler: &Sampler) -> Self {
+        let (wrap_u, wrap_v, wrap_p) = sampler.get_wrap_mode();
+        let (lod_clamp_min, lod_clamp_max) = sampler.get_lod_clamp();
+        let (compare_enable, compare_op) = sampler.get_depth_compare();
+        let border_color = match sampler.get_border_color_type() {
+            BorderColorType::Float => {
+                let (r, g, b, a) = sampler.get_border_color();
+                BorderColor::Float([r, g, b, a])
+            }
+            BorderColorType::Uint => {
+                let (r, g, b, a) = sampler.get_border_color_ui();
+                BorderColor::Uint([r, g, b, a])
+            }
+            BorderColorType::Sint => {
+                let (r, g, b, a) = sampler.get_border_color_i();
+                BorderColor::Sint([r, g, b, a])
+            }
+        };
+
+        SamplerDesc {
+            min_filter: sampler.get_min_filter(),
+            mag_filter: sampler.get_mag_filter(),
+            mip_filter: sampler.get_mip_filter(),
+            wrap_mode: [wrap_u, wrap_v, wrap_p],
+            lod_clamp_min,
+            lod_clamp_max,
+            lod_bias: sampler.get_lod_bias(),
+            lod_snap: sampler.get_lod_snap(),
+            compare_enable,
+            compare_op,
+            border_color,
+            max_anisotropy: sampler.get_max_anisotropy(),
+            reduction_mode: sampler.get_reduction_mode(),
+        }
+    }
+}
+
+impl From<SamplerDesc> for Sampler {
+    fn from(desc: SamplerDesc) -> Self {
+        let [wrap_u, wrap_v, wrap_p] = desc.wrap_mode;
+
+        let mut sampler = Sampler::new();
+        sampler
+            .set_filter(desc.min_filter, desc.mag_filter, desc.mip_filter)
+            .set_wrap_mode(wrap_u, wrap_v, wrap_p)
+            .set_lod_clamp(desc.lod_clamp_min, desc.lod_clamp_max)
+            .set_lod_bias(desc.lod_bias)
+            .set_lod_snap(desc.lod_snap)
+            .set_depth_compare(desc.compare_enable, desc.compare_op)
+            .set_max_anisotropy(desc.max_anisotropy)
+            .set_reduction_mode(desc.reduction_mode);
+
+        match desc.border_color {
+            BorderColor::Float([r, g, b, a]) => sampler.set_border_color(r, g, b, a),
+            BorderColor::Uint([r, g, b, a]) => sampler.set_border_color_ui(r, g, b, a),
+            BorderColor::Sint([r, g, b, a]) => sampler.set_border_color_i(r, g, b, a),
+        };
+        sampler
+    }
+}
+
+impl_serde_via!(Sampler, SamplerDesc);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "VtxAttribState")]
+struct VtxAttribStateDesc {
+    buffer_id: u8,
+    is_fixed: bool,
+    offset: u16,
+    size: VtxAttribSize,
+    #[serde(rename = "type")]
+    type_: VtxAttribType,
+    is_bgra: bool,
+}
+
+impl From<&VtxAttribState> for VtxAttribStateDesc {
+    fn from(state: &VtxAttribState) -> Self {
+        VtxAttribStateDesc {
+            buffer_id: state.get_buffer_id(),
+            is_fixed: state.get_is_fixed(),
+            offset: state.get_offset(),
+            size: state.get_size(),
+            type_: state.get_type(),
+            is_bgra: state.get_is_bgra(),
+        }
+    }
+}
+
+impl From<VtxAttribStateDesc> for VtxAttribState {
+    fn from(desc: VtxAttribStateDesc) -> Self {
+        let mut state = VtxAttribState::new();
+        state
+            .set_buffer_id(desc.buffer_id)
+            .set_is_fixed(desc.is_fixed)
+            .set_offset(desc.offset)
+            .set_size(desc.size)
+            .set_type(desc.type_)
+            .set_is_bgra(desc.is_bgra);
+        state
+    }
+}
+
+impl_serde_via!(VtxAttribState, VtxAttribStateDesc);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Serializing the parsed value again has to give back the same document.
+    fn assert_round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) {
+        let json = serde_json::to_string(value).unwrap();
+        let parsed: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn color_mask_uses_channel_names() {
+        assert_eq!(serde_json::to_string(&ColorMask::RGB).unwrap(), r#""RGB""#);
+        assert_eq!(serde_json::to_string(&ColorMask::empty()).unwrap(), r#""""#);
+        assert_eq!(serde_json::from_str::<ColorMask>(r#""AR""#).unwrap(), ColorMask::R | ColorMask::A);
+        assert!(serde_json::from_str::<ColorMask>(r#""RX""#).is_err());
+        assert!(serde_json::from_str::<ColorMask>("15").is_err());
+    }
+
+    #[test]
+    fn rasterizer_state_round_trips() {
+        let mut state = RasterizerState::new();
+        state.set_cull_mode(Face::Front).set_polygon_mode_front(PolygonMode::Line).set_depth_bias_enable_mask(2);
+        assert_round_trip(&state);
+    }
+
+    #[test]
+    fn multisample_state_round_trips() {
+        let mut state = MultisampleState::new();
+        state.set_mode(MsMode::Ms4x).set_alpha_to_coverage_enable(true);
+        assert_round_trip(&state);
+    }
+
+    #[test]
+    fn color_state_round_trips() {
+        let mut state = ColorState::new();
+        state.set_blend_enable_mask(5).set_logic_op(LogicOp::And).set_alpha_compare_op(CompareOp::Less);
+        assert_round_trip(&state);
+    }
+
+    #[test]
+    fn color_write_state_round_trips() {
+        let mut state = ColorWriteState::new();
+        state.set_mask(0, ColorMask::RGB.bits()).set_mask(3, ColorMask::A.bits());
+        assert_round_trip(&state);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.starts_with(r#"{"masks":["RGB","RGBA","RGBA","A""#), "{json}");
+    }
+
+    #[test]
+    fn blend_state_round_trips() {
+        let mut state = BlendState::new();
+        state.set_ops(BlendOp::Sub, BlendOp::RevSub).set_factors(
+            BlendFactor::SrcColor,
+            BlendFactor::One,
+            BlendFactor::Zero,
+            BlendFactor::One,
+        );
+        assert_round_trip(&state);
+    }
+
+    #[test]
+    fn depth_stencil_state_round_trips() {
+        let mut state = DepthStencilState::new();
+        state
+            .set_depth_compare_op(CompareOp::Equal)
+            .set_stencil_test_enable(true)
+            .set_stencil_front_pass_op(StencilOp::Replace)
+            .set_stencil_back_fail_op(StencilOp::Zero);
+        assert_round_trip(&state);
+    }
+
+    #[test]
+    fn sampler_round_trips() {
+        let mut sampler = Sampler::new();
+        sampler
+            .set_filter(Filter::Linear, Filter::Nearest, MipFilter::Linear)
+            .set_wrap_mode(WrapMode::Repeat, WrapMode::MirroredRepeat, WrapMode::ClampToEdge)
+            .set_lod_bias(0.5)
+            .set_border_color_ui(1, 2, 3, 4);
+        assert_round_trip(&sampler);
+    }
+
+    #[test]
+    fn vtx_attrib_state_round_trips() {
+        let mut state = VtxAttribState::new();
+        state
+            .set_buffer_id(1)
+            .set_offset(12)
+            .set_size(VtxAttribSize::_3x32)
+            .set_type(VtxAttribType::Unorm)
+            .set_is_bgra(true);
+        assert_round_trip(&state);
+    }
+}
@@ -0,0 +1,159 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use deko3d_sys::*;
+
+use crate::{CmdList, DekoError, DkResultExt, Fence, Queue, Result};
+
+// How long the waiter thread blocks on a single fence before checking the others.
+const WAITER_POLL_NS: i64 = 1_000_000;
+
+// Shared between a pending fence future and the waiter thread. The future replaces the waker when it
+// is polled again, and dropping the future leaves the waiter holding the only reference.
+#[derive(Default)]
+pub(crate) struct WakerSlot {
+    waker: Option<Waker>,
+    // Whether the waiter thread is still watching the fence for this slot.
+    registered: bool,
+}
+
+struct PendingFence(DkFence, Arc<Mutex<WakerSlot>>);
+
+#[derive(Default)]
+struct FenceWaiter {
+    pending: Mutex<Vec<PendingFence>>,
+    cond: Condvar,
+}
+
+impl FenceWaiter {
+    fn get() -> &'static FenceWaiter {
+        static WAITER: OnceLock<FenceWaiter> = OnceLock::new();
+        static THREAD: OnceLock<()> = OnceLock::new();
+
+        let waiter = WAITER.get_or_init(FenceWaiter::default);
+        THREAD.get_or_init(|| {
+            std::thread::Builder::new()
+                .name("deko3d-fence-waiter".into())
+                .spawn(move || waiter.run())
+                .expect("failed to spawn fence waiter thread");
+        });
+
+        waiter
+    }
+
+    fn register(&self, fence: DkFence, slot: Arc<Mutex<WakerSlot>>) {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner).push(PendingFence(fence, slot));
+        self.cond.notify_one();
+    }
+
+    fn run(&self) {
+        let mut waiting = Vec::new();
+
+        loop {
+            {
+                let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+                while pending.is_empty() && waiting.is_empty() {
+                    pending = self.cond.wait(pending).unwrap_or_else(PoisonError::into_inner);
+                }
+                waiting.append(&mut pending);
+            }
+
+            // The waiter works on copies of the fences, so futures can be dropped at any time.
+            waiting.retain_mut(|PendingFence(fence, slot)| {
+                if Arc::strong_count(slot) == 1 {
+                    return false;
+                }
+
+                match unsafe { dkFenceWait(fence, WAITER_POLL_NS) }.into_result() {
+                    Err(DekoError::Timeout) => true,
+                    _ => {
+                        let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+                        slot.registered = false;
+                        if let Some(waker) = slot.waker.take() {
+                            waker.wake();
+                        }
+                        false
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl Future for Fence {
+    type Output = Result;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result> {
+        match self.wait(0) {
+            Err(DekoError::Timeout) => {
+                // The fence is only handed to the waiter again once it is done with the previous
+                // registration, e.g. after the fence was signaled anew; other polls just update the waker.
+                let fence = self.0;
+                let slot = self.1.get_or_insert_with(Default::default);
+                let mut state = slot.lock().unwrap_or_else(PoisonError::into_inner);
+
+                if !state.waker.as_ref().is_some_and(|waker| waker.will_wake(cx.waker())) {
+                    state.waker = Some(cx.waker().clone());
+                }
+                if !state.registered {
+                    state.registered = true;
+                    FenceWaiter::get().register(fence, slot.clone());
+                }
+                Poll::Pending
+            }
+            result => {
+                self.1 = None;
+                Poll::Ready(result)
+            }
+        }
+    }
+}
+
+/// Completion handle for a batch of commands submitted with [`Queue::submit_tracked`].
+pub struct SubmissionToken(pub(crate) Fence);
+
+impl SubmissionToken {
+    pub fn get_fence(&self) -> &Fence {
+        &self.0
+    }
+
+    pub fn into_fence(self) -> Fence {
+        self.0
+    }
+
+    pub fn is_complete(&mut self) -> bool {
+        self.0.wait(0) != Err(DekoError::Timeout)
+    }
+
+    pub fn wait(&mut self) -> Result {
+        self.0.wait_no_timeout()
+    }
+
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result {
+        self.0.wait_timeout(timeout)
+    }
+}
+
+impl Future for SubmissionToken {
+    type Output = Result;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl Queue {
+    /// Submits `cmds` and flushes the queue, returning a token that completes once the GPU
+    /// has executed them.
+    pub fn submit_tracked(&self, cmds: CmdList) -> SubmissionToken {
+        let mut fence = Fence::new();
+
+        self.submit_commands(cmds);
+        self.signal_fence(&mut fence, true);
+
+        SubmissionToken(fence)
+    }
+}
@@ -0,0 +1,45 @@
+//! Compile-time checks for the threading contract of the handle types.
+//!
+//! `Device`, `MemBlock` and the pools are `Send + Sync`. `CmdBuf`, `Queue` and `Swapchain` are
+//! `Send` only: move them to the thread that uses them, or put them behind a `Mutex`.
+
+use crate::*;
+
+const fn assert_send<T: Send>() {}
+const fn assert_sync<T: Sync>() {}
+
+const _: () = {
+    assert_send::<Device>();
+    assert_sync::<Device>();
+    assert_send::<MemBlock>();
+    assert_sync::<MemBlock>();
+    assert_send::<CmdBuf<'static>>();
+    assert_send::<CmdList<'static>>();
+    assert_sync::<CmdList<'static>>();
+    assert_send::<Queue>();
+    assert_send::<Swapchain>();
+    assert_send::<Fence>();
+    assert_send::<CmdMemPool<'static>>();
+    assert_sync::<CmdMemPool<'static>>();
+    assert_send::<TimelinePool<'static>>();
+    assert_sync::<TimelinePool<'static>>();
+    assert_send::<Timeline<'static>>();
+    assert_sync::<Timeline<'static>>();
+    assert_send::<DeferredDeleter>();
+    assert_sync::<DeferredDeleter>();
+    assert_send::<SubmissionToken>();
+};
+
+// Resolving `some_item` is ambiguous for `Sync` types, which fails to compile.
+trait AmbiguousIfSync<A> {
+    fn some_item() {}
+}
+
+impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+
+const _: fn() = || {
+    let _ = <CmdBuf<'static> as AmbiguousIfSync<_>>::some_item;
+    let _ = <Queue as AmbiguousIfSync<_>>::some_item;
+    let _ = <Swapchain as AmbiguousIfSync<_>>::some_item;
+};
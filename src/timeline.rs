@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use deko3d_sys::*;
+
+use crate::pool::SharedPool;
+use crate::{
+    CmdBuf, DekoError, Device, Fence, MemBlock, MemBlockFlags, MemBlockMaker, PipelinePos, Queue, Result, VarCompareOp,
+    VarOp, Variable,
+};
+
+const VARIABLE_SIZE: u32 = 4;
+const VARIABLES_PER_BLOCK: u32 = DK_MEMBLOCK_ALIGNMENT / VARIABLE_SIZE;
+
+struct TimelinePoolState {
+    blocks: Vec<MemBlock>,
+    free: Vec<(usize, u32)>,
+    retired: Vec<(Fence, (usize, u32))>,
+}
+
+impl TimelinePoolState {
+    fn alloc(&mut self, device: &Device) -> (usize, u32) {
+        let free = &mut self.free;
+        self.retired.retain_mut(|(fence, slot)| {
+            let done = fence.wait(0) != Err(DekoError::Timeout);
+            if done {
+                free.push(*slot);
+            }
+            !done
+        });
+
+        if let Some(slot) = self.free.pop() {
+            return slot;
+        }
+
+        let block = MemBlockMaker::new(device, DK_MEMBLOCK_ALIGNMENT)
+            .set_flags(MemBlockFlags::CpuUncached | MemBlockFlags::GpuCached)
+            .create();
+
+        let index = self.blocks.len();
+        self.blocks.push(block);
+        self.free.extend((1..VARIABLES_PER_BLOCK).rev().map(|i| (index, i * VARIABLE_SIZE)));
+
+        (index, 0)
+    }
+}
+
+impl Drop for TimelinePoolState {
+    fn drop(&mut self) {
+        for (fence, _) in self.retired.iter_mut() {
+            let _ = fence.wait_no_timeout();
+        }
+    }
+}
+
+/// Shared backing storage for [`Timeline`] variables.
+#[derive(Clone)]
+pub struct TimelinePool<'d>(Arc<SharedPool<'d, TimelinePoolState>>);
+
+impl<'d> TimelinePool<'d> {
+    pub fn new(device: &'d Device) -> Self {
+        let state = TimelinePoolState { blocks: Vec::new(), free: Vec::new(), retired: Vec::new() };
+        TimelinePool(Arc::new(SharedPool::new(device, state)))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TimelinePoolState> {
+        self.0.lock()
+    }
+}
+
+/// A monotonically increasing counter that the GPU and the CPU can signal and wait on.
+///
+/// Values are compared sequentially, so they may wrap around as long as waiters are never more
+/// than 2^31 steps behind.
+///
+/// Dropping a timeline hands its variable straight back to the pool, so the GPU must be done with
+/// every submitted command that signals or waits on it. Use [`Timeline::retire`] or
+/// [`Timeline::retire_after`] to release it while such commands may still be in flight.
+pub struct Timeline<'d> {
+    var: Variable,
+    slot: (usize, u32),
+    retire_fence: Option<Fence>,
+    pool: TimelinePool<'d>,
+}
+
+impl<'d> Timeline<'d> {
+    pub fn new(pool: &TimelinePool<'d>, initial_value: u32) -> Self {
+        let mut state = pool.lock();
+        let slot = state.alloc(pool.0.get_device());
+
+        let mut var = unsafe { Variable::from_raw(std::mem::zeroed()) };
+        var.initialize(&state.blocks[slot.0], slot.1);
+        var.signal(VarOp::Set, initial_value);
+
+        Timeline { var, slot, retire_fence: None, pool: pool.clone() }
+    }
+
+    /// Releases the timeline, keeping its variable out of the pool until `fence` has signaled.
+    pub fn retire(mut self, fence: Fence) {
+        self.retire_fence = Some(fence);
+    }
+
+    /// Releases the timeline once everything submitted to `queue` so far has completed.
+    pub fn retire_after(self, queue: &Queue) {
+        let mut fence = Fence::new();
+        queue.signal_fence(&mut fence, true);
+
+        self.retire(fence);
+    }
+
+    pub fn get_variable(&self) -> &Variable {
+        &self.var
+    }
+
+    pub fn value(&self) -> u32 {
+        self.var.read()
+    }
+
+    pub fn is_reached(&self, value: u32) -> bool {
+        self.value().wrapping_sub(value) as i32 >= 0
+    }
+
+    pub fn signal_on_gpu(&self, cmd_buf: &CmdBuf, value: u32, pos: PipelinePos) {
+        cmd_buf.signal_variable(&self.var, VarOp::Set, value, pos);
+    }
+
+    pub fn wait_on_gpu(&self, cmd_buf: &CmdBuf, value: u32) {
+        cmd_buf.wait_variable(&self.var, VarCompareOp::Sequential, value);
+    }
+
+    pub fn signal_from_cpu(&self, value: u32) {
+        self.var.signal(VarOp::Set, value);
+    }
+
+    pub fn wait_for(&self, value: u32, timeout: Duration) -> Result {
+        let start = Instant::now();
+        let mut backoff = Duration::from_micros(10);
+
+        while !self.is_reached(value) {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(DekoError::Timeout);
+            }
+
+            std::thread::sleep(backoff.min(timeout - elapsed));
+            backoff = (backoff * 2).min(Duration::from_millis(1));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Timeline<'_> {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        match self.retire_fence.take() {
+            Some(fence) => state.retired.push((fence, self.slot)),
+            None => state.free.push(self.slot),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use crate::{GpuAddr, MemBlock, DK_UNIFORM_BUF_ALIGNMENT, DK_UNIFORM_BUF_MAX_SIZE};
+
+// Two to four element arrays of scalars are treated as GLSL vectors, everything else follows the
+// array rules of the respective layout.
+
+/// A type whose Rust layout matches the GLSL std140 layout.
+///
+/// # Safety
+///
+/// The size, field offsets and padding of the type must match std140, and `STD140_ALIGN` must be its
+/// std140 base alignment. Use `#[derive(Std140)]` rather than implementing this by hand.
+pub unsafe trait Std140: Copy {
+    const STD140_ALIGN: usize;
+
+    #[doc(hidden)]
+    const IS_SCALAR: bool = false;
+}
+
+/// A type whose Rust layout matches the GLSL std430 layout.
+///
+/// # Safety
+///
+/// The size, field offsets and padding of the type must match std430, and `STD430_ALIGN` must be its
+/// std430 base alignment. Use `#[derive(Std430)]` rather than implementing this by hand.
+pub unsafe trait Std430: Copy {
+    const STD430_ALIGN: usize;
+
+    #[doc(hidden)]
+    const IS_SCALAR: bool = false;
+}
+
+macro_rules! impl_std_layout_scalar {
+    ($($ty:ty),*) => {
+        $(
+            unsafe impl Std140 for $ty {
+                const STD140_ALIGN: usize = 4;
+                const IS_SCALAR: bool = true;
+            }
+
+            unsafe impl Std430 for $ty {
+                const STD430_ALIGN: usize = 4;
+                const IS_SCALAR: bool = true;
+            }
+        )*
+    };
+}
+
+impl_std_layout_scalar!(f32, u32, i32);
+
+unsafe impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const STD140_ALIGN: usize = if T::IS_SCALAR && N == 2 {
+        2 * T::STD140_ALIGN
+    } else if T::IS_SCALAR && (N == 3 || N == 4) {
+        4 * T::STD140_ALIGN
+    } else {
+        assert!(std::mem::size_of::<T>() % 16 == 0, "std140 array elements must be padded to a multiple of 16 bytes");
+        16
+    };
+}
+
+unsafe impl<T: Std430, const N: usize> Std430 for [T; N] {
+    const STD430_ALIGN: usize = if T::IS_SCALAR && N == 2 {
+        2 * T::STD430_ALIGN
+    } else if T::IS_SCALAR && (N == 3 || N == 4) {
+        4 * T::STD430_ALIGN
+    } else {
+        assert!(
+            std::mem::size_of::<T>() % T::STD430_ALIGN == 0,
+            "std430 array elements must be padded to a multiple of their base alignment"
+        );
+        T::STD430_ALIGN
+    };
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UniformBuffer<T: Std140> {
+    addr: GpuAddr,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    pub const SIZE: u32 = {
+        let size = std::mem::size_of::<T>() as u32;
+        assert!(size <= DK_UNIFORM_BUF_MAX_SIZE, "uniform block exceeds the maximum uniform buffer size");
+        (size + DK_UNIFORM_BUF_ALIGNMENT - 1) & !(DK_UNIFORM_BUF_ALIGNMENT - 1)
+    };
+
+    pub fn new(addr: GpuAddr) -> Self {
+        assert_eq!(addr % DK_UNIFORM_BUF_ALIGNMENT as u64, 0, "misaligned uniform buffer address");
+
+        UniformBuffer { addr, _marker: PhantomData }
+    }
+
+    pub fn from_mem_block(mem: &MemBlock, offset: u32) -> Self {
+        assert!(offset as u64 + Self::SIZE as u64 <= mem.get_size() as u64, "uniform buffer exceeds the memory block");
+
+        Self::new(mem.get_gpu_addr() + offset as u64)
+    }
+
+    pub fn get_gpu_addr(&self) -> GpuAddr {
+        self.addr
+    }
+
+    pub fn get_size(&self) -> u32 {
+        Self::SIZE
+    }
+}
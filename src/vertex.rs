@@ -0,0 +1,86 @@
+pub use deko3d_image::Half;
+
+use crate::{VtxAttribSize, VtxAttribState, VtxAttribType, VtxBufferState};
+
+pub trait Vertex {
+    type AttribStates: AsRef<[VtxAttribState]>;
+
+    const DIVISOR: u32;
+
+    fn vtx_attrib_state(buffer_id: u8) -> Self::AttribStates;
+
+    fn vtx_buffer_state() -> VtxBufferState
+    where
+        Self: Sized,
+    {
+        VtxBufferState { stride: std::mem::size_of::<Self>() as u32, divisor: Self::DIVISOR }
+    }
+}
+
+pub trait VertexAttrib {
+    const SIZE: VtxAttribSize;
+    const TYPE: VtxAttribType;
+}
+
+macro_rules! impl_vertex_attrib {
+    ($ty:ty, $type_:ident, $size1:ident, $size2:ident, $size3:ident, $size4:ident) => {
+        impl VertexAttrib for $ty {
+            const SIZE: VtxAttribSize = VtxAttribSize::$size1;
+            const TYPE: VtxAttribType = VtxAttribType::$type_;
+        }
+
+        impl VertexAttrib for [$ty; 1] {
+            const SIZE: VtxAttribSize = VtxAttribSize::$size1;
+            const TYPE: VtxAttribType = VtxAttribType::$type_;
+        }
+
+        impl VertexAttrib for [$ty; 2] {
+            const SIZE: VtxAttribSize = VtxAttribSize::$size2;
+            const TYPE: VtxAttribType = VtxAttribType::$type_;
+        }
+
+        impl VertexAttrib for [$ty; 3] {
+            const SIZE: VtxAttribSize = VtxAttribSize::$size3;
+            const TYPE: VtxAttribType = VtxAttribType::$type_;
+        }
+
+        impl VertexAttrib for [$ty; 4] {
+            const SIZE: VtxAttribSize = VtxAttribSize::$size4;
+            const TYPE: VtxAttribType = VtxAttribType::$type_;
+        }
+    };
+}
+
+impl_vertex_attrib!(f32, Float, _1x32, _2x32, _3x32, _4x32);
+impl_vertex_attrib!(u32, Uint, _1x32, _2x32, _3x32, _4x32);
+impl_vertex_attrib!(i32, Sint, _1x32, _2x32, _3x32, _4x32);
+impl_vertex_attrib!(Half, Float, _1x16, _2x16, _3x16, _4x16);
+impl_vertex_attrib!(u16, Uint, _1x16, _2x16, _3x16, _4x16);
+impl_vertex_attrib!(i16, Sint, _1x16, _2x16, _3x16, _4x16);
+impl_vertex_attrib!(u8, Uint, _1x8, _2x8, _3x8, _4x8);
+impl_vertex_attrib!(i8, Sint, _1x8, _2x8, _3x8, _4x8);
+
+impl VtxAttribType {
+    pub const fn normalized(self) -> Self {
+        match self {
+            VtxAttribType::Uint => VtxAttribType::Unorm,
+            VtxAttribType::Sint => VtxAttribType::Snorm,
+            _ => panic!("only integer vertex attributes can be normalized"),
+        }
+    }
+}
+
+impl VtxAttribState {
+    pub fn for_attrib<T: VertexAttrib>(buffer_id: u8, offset: u16, normalized: bool, is_bgra: bool) -> Self {
+        let mut state = VtxAttribState::new();
+
+        state
+            .set_buffer_id(buffer_id)
+            .set_offset(offset)
+            .set_size(T::SIZE)
+            .set_type(if normalized { T::TYPE.normalized() } else { T::TYPE })
+            .set_is_bgra(is_bgra);
+
+        state
+    }
+}